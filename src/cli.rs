@@ -19,7 +19,22 @@ pub struct Cli {
 /// Select the mode to run the agent in.
 #[derive(Clone, Debug, Subcommand)]
 pub enum Mode {
+    /// Run the agent in ConfigServer mode (for members of a Sharded cluster config servers).
+    #[command(alias = "configsvr")]
+    ConfigServer,
+
+    /// Run the agent in Mongos mode (for routers of a Sharded cluster).
+    #[command(alias = "router")]
+    Mongos,
+
     /// Run the agent in ReplicaSet mode (for members of a Replica Set cluster).
     #[command(alias = "rs", alias = "replica", alias = "replicaset")]
     ReplicaSet,
+
+    /// Run the agent in Shard mode (for members of a Shard Replica Set in a Sharded cluster).
+    #[command(alias = "shardsvr")]
+    Shard,
+
+    /// Run the agent in Standalone mode (for non-replicated mongod nodes).
+    Standalone,
 }
@@ -10,10 +10,55 @@ use opentelemetry::trace::FutureExt;
 
 use replisdk::utils::trace::TraceFutureStdErrExt;
 
+use crate::constants::CMD_BUILD_INFO;
+use crate::constants::CMD_HELLO;
+use crate::constants::CMD_IS_MASTER;
+use crate::constants::CMD_PING;
 use crate::constants::CMD_REPL_SET_GET_STATUS;
+use crate::constants::CMD_SERVER_STATUS;
+use crate::constants::COMMAND_NOT_FOUND;
 use crate::constants::DB_ADMIN;
+use crate::constants::NO_REPLICATION_ENABLED;
 use crate::constants::REPL_SET_NOT_INITIALISED;
 
+/// Run the buildInfo command against the DB.
+///
+/// ## Errors Telemetry
+///
+/// This function does not report errors from the MongoDB server as error
+/// as part of the generated telemetry data.
+/// If error information from this function should be attached to telemetry data then
+/// it should be done by the caller.
+pub async fn build_info(client: &Client) -> MdbResult<Document> {
+    simple_command(client, CMD_BUILD_INFO).await
+}
+
+/// Run the hello command against the DB (falling back to isMaster for older servers).
+///
+/// ## Errors Telemetry
+///
+/// This function does not report errors from the MongoDB server as error
+/// as part of the generated telemetry data.
+/// This is because callers may use failures to determine the state of the node.
+pub async fn hello(client: &Client) -> MdbResult<Document> {
+    let result = simple_command(client, CMD_HELLO).await;
+    match result {
+        Err(error) if command_not_found(&error) => simple_command(client, CMD_IS_MASTER).await,
+        result => result,
+    }
+}
+
+/// Run the ping command against the DB.
+///
+/// ## Errors Telemetry
+///
+/// This function does not report errors from the MongoDB server as error
+/// as part of the generated telemetry data.
+/// This is because callers may use failures to determine the state of the node.
+pub async fn ping(client: &Client) -> MdbResult<Document> {
+    simple_command(client, CMD_PING).await
+}
+
 /// Run the replSetGetStatus command against the DB.
 ///
 /// ## Errors Telemetry
@@ -42,6 +87,94 @@ pub async fn replica_set_status(client: &Client) -> MdbResult<Document> {
         .await
 }
 
+/// Run the serverStatus command against the DB.
+///
+/// ## Errors Telemetry
+///
+/// This function does not report errors from the MongoDB server as error
+/// as part of the generated telemetry data.
+/// This is because callers may use failures to determine the state of the node.
+pub async fn server_status(client: &Client) -> MdbResult<Document> {
+    simple_command(client, CMD_SERVER_STATUS).await
+}
+
+/// Run a command that takes no arguments against the admin DB.
+async fn simple_command(client: &Client, name: &str) -> MdbResult<Document> {
+    let trace = crate::trace::mongodb_client_context(name);
+    let (_, _timer) = crate::metrics::observe_mongodb_op(name);
+
+    let command = {
+        let mut command = Document::new();
+        command.insert(name, 1);
+        command
+    };
+    let admin = client.database(DB_ADMIN);
+    admin
+        .run_command(command)
+        .into_future()
+        .trace_on_err()
+        .with_context(trace)
+        .await
+}
+
+/// Check MongoDB client errors to see if the command is not supported by the server.
+fn command_not_found(error: &Error) -> bool {
+    if let ErrorKind::Command(ref inner) = *error.kind {
+        return inner.code == COMMAND_NOT_FOUND;
+    }
+    false
+}
+
+/// Check MongoDB client errors to see if they are caused by connection issues.
+///
+/// Connection issues suggest the store process is down or otherwise not reachable.
+pub fn connection_failed(error: &Error) -> bool {
+    matches!(
+        *error.kind,
+        ErrorKind::Authentication { .. }
+            | ErrorKind::ConnectionPoolCleared { .. }
+            | ErrorKind::Io(_)
+            | ErrorKind::ServerSelection { .. }
+    )
+}
+
+/// Check MongoDB client errors to see if the server closed the connection.
+///
+/// Before MongoDB 4.2 primaries close all client connections when they step down,
+/// including the connection used to request the step down.
+/// Only I/O errors caused by the server closing the connection are matched so that
+/// other connection issues (timeouts, DNS or TLS errors, ...) are still reported.
+pub fn connection_closed(error: &Error) -> bool {
+    let error = match *error.kind {
+        ErrorKind::Io(ref error) => error,
+        _ => return false,
+    };
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Number of pending requests to enter maintenance mode reported by [`replica_set_status`].
+///
+/// Nodes that are not in maintenance mode do not report the count so zero is returned.
+pub fn maintenance_requests(status: &Document) -> i32 {
+    status.get_i32("maintenanceMode").unwrap_or(0)
+}
+
+/// Check [`replica_set_status`]'s errors to see if the node is running without replication.
+///
+/// This is the case for standalone nodes started without the `--replSet` option.
+pub fn replication_not_enabled(error: &Error) -> bool {
+    if let ErrorKind::Command(ref inner) = *error.kind {
+        return inner.code == NO_REPLICATION_ENABLED;
+    }
+    false
+}
+
 /// Check [`replica_set_status`]'s errors to see if the Replica Set is not initialised.
 ///
 /// This function only returns true if the error indicated the replica set is NOT initialised.
@@ -52,3 +185,33 @@ pub fn replica_set_not_initialised(error: &Error) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use mongodb::error::Error;
+    use mongodb::error::ErrorKind;
+
+    use super::connection_closed;
+
+    fn io_error(kind: std::io::ErrorKind) -> Error {
+        Error::from(ErrorKind::from(std::io::Error::from(kind)))
+    }
+
+    #[test]
+    fn closed_by_server() {
+        assert!(connection_closed(&io_error(
+            std::io::ErrorKind::UnexpectedEof
+        )));
+        assert!(connection_closed(&io_error(
+            std::io::ErrorKind::ConnectionReset
+        )));
+    }
+
+    #[test]
+    fn other_connection_errors() {
+        assert!(!connection_closed(&io_error(std::io::ErrorKind::TimedOut)));
+        assert!(!connection_closed(&io_error(
+            std::io::ErrorKind::ConnectionRefused
+        )));
+    }
+}
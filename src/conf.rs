@@ -62,6 +62,24 @@ pub struct Conf {
     /// A value of zero means that connections will not be closed for being idle.
     pub max_idle_time: Option<u64>,
 
+    /// Interval in seconds to sample MongoDB server metrics at.
+    ///
+    /// Server metrics are not collected if this is not set or is set to zero.
+    #[serde(default)]
+    pub server_metrics_interval: Option<u64>,
+
+    /// Sections of `serverStatus` to report as store attributes.
+    ///
+    /// All sections are reported if this is not set.
+    #[serde(default)]
+    pub server_status_attributes: Option<Vec<ServerStatusSection>>,
+
+    /// Time in milliseconds to reuse `replSetGetStatus` results across node information requests.
+    ///
+    /// Defaults to one second. A value of zero means results are never reused.
+    #[serde(default)]
+    pub status_snapshot_ttl: Option<u64>,
+
     /// TLS configuration for connections to the server.
     #[serde(default)]
     pub tls: Option<Tls>,
@@ -91,8 +109,39 @@ pub enum ConfError {
     Open(String),
 }
 
+/// Sections of the `serverStatus` output that can be reported as store attributes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerStatusSection {
+    /// Current and available incoming connections.
+    Connections,
+
+    /// Host name, process name and PID of the server.
+    Host,
+
+    /// Name of the storage engine in use.
+    StorageEngine,
+
+    /// Number of seconds the server has been running for.
+    Uptime,
+
+    /// Configured size and current usage of the WiredTiger cache.
+    WiredTigerCache,
+}
+
+impl ServerStatusSection {
+    /// All sections of the `serverStatus` output that can be reported.
+    pub const ALL: &'static [ServerStatusSection] = &[
+        ServerStatusSection::Connections,
+        ServerStatusSection::Host,
+        ServerStatusSection::StorageEngine,
+        ServerStatusSection::Uptime,
+        ServerStatusSection::WiredTigerCache,
+    ];
+}
+
 /// Configure MongoDB version detection strategies.
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct VersionDetect {
     /// Override default store detection command.
     #[serde(default)]
@@ -101,6 +150,44 @@ pub struct VersionDetect {
     /// Optional file to detect the MongoDB version from.
     #[serde(default)]
     pub file: Option<String>,
+
+    /// Order in which version detection strategies are tried.
+    #[serde(default = "VersionDetect::default_order")]
+    pub order: Vec<VersionStrategy>,
+}
+
+impl VersionDetect {
+    fn default_order() -> Vec<VersionStrategy> {
+        vec![
+            VersionStrategy::BuildInfo,
+            VersionStrategy::Command,
+            VersionStrategy::File,
+        ]
+    }
+}
+
+impl Default for VersionDetect {
+    fn default() -> Self {
+        VersionDetect {
+            command: None,
+            file: None,
+            order: Self::default_order(),
+        }
+    }
+}
+
+/// Supported strategies to detect the MongoDB version with.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionStrategy {
+    /// Ask the server with the `buildInfo` command.
+    BuildInfo,
+
+    /// Run the MongoDB binary with the `--version` flag.
+    Command,
+
+    /// Read the version from the configured file, if any.
+    File,
 }
 
 /// MongoDB authentication credentials and mode.
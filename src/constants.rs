@@ -5,17 +5,41 @@ use replisdk::agent::models::ShardRole;
 
 use crate::errors::MemberStateParseError;
 
+/// Prefix for MongoDB specific agent actions.
+pub const ACTION_PREFIX: &str = "mongodb.com";
+
 /// Prefix for MongoDB attributes.
 pub const ATTRIBUTE_PREFIX: &str = "mongodb.com";
 
+/// MongoDB command to get server build information.
+pub const CMD_BUILD_INFO: &str = "buildInfo";
+
+/// MongoDB command to get collection statistics.
+pub const CMD_COLL_STATS: &str = "collStats";
+
 /// MongoDB command to get server command line and configuration.
 pub const CMD_GET_CMD_LINE_OPTS: &str = "getCmdLineOpts";
 
+/// MongoDB command to get the cluster-wide default read and write concerns.
+pub const CMD_GET_DEFAULT_RW_CONCERN: &str = "getDefaultRWConcern";
+
 /// MongoDB command to get server parameters.
 pub const CMD_GET_PARAMETER: &str = "getParameter";
 
-/// MongoDB command to get collection statistics.
-pub const CMD_COLL_STATS: &str = "collStats";
+/// MongoDB command to describe the role of the node the client is connected to.
+pub const CMD_HELLO: &str = "hello";
+
+/// Legacy MongoDB command to describe the role of the node (for servers without [`CMD_HELLO`]).
+pub const CMD_IS_MASTER: &str = "isMaster";
+
+/// MongoDB command to list shards in a sharded cluster (mongos only).
+pub const CMD_LIST_SHARDS: &str = "listShards";
+
+/// MongoDB command to check the server is responsive.
+pub const CMD_PING: &str = "ping";
+
+/// MongoDB command to prevent a Replica Set member from seeking election.
+pub const CMD_REPL_SET_FREEZE: &str = "replSetFreeze";
 
 /// MongoDB command to get the current Replica Set configuration.
 pub const CMD_REPL_SET_GET_CONFIG: &str = "replSetGetConfig";
@@ -26,21 +50,60 @@ pub const CMD_REPL_SET_GET_STATUS: &str = "replSetGetStatus";
 /// MongoDB command to initialise a new Replica Set.
 pub const CMD_REPL_SET_INIT: &str = "replSetInitiate";
 
+/// MongoDB command to move a Replica Set secondary in or out of maintenance mode.
+pub const CMD_REPL_SET_MAINTENANCE: &str = "replSetMaintenance";
+
 /// MongoDB command to get the current Replica Set configuration.
 pub const CMD_REPL_SET_RECONFIG: &str = "replSetReconfig";
 
+/// MongoDB command to change the maximum size of the oplog.
+pub const CMD_REPL_SET_RESIZE_OPLOG: &str = "replSetResizeOplog";
+
+/// MongoDB command to step down the Replica Set primary.
+pub const CMD_REPL_SET_STEP_DOWN: &str = "replSetStepDown";
+
+/// MongoDB command to get an overview of the server state.
+pub const CMD_SERVER_STATUS: &str = "serverStatus";
+
+/// MongoDB command to set the feature compatibility version (FCV).
+pub const CMD_SET_FEATURE_COMPATIBILITY_VERSION: &str = "setFeatureCompatibilityVersion";
+
+/// Name of the collection storing the Replica Set oplog (in the [`DB_LOCAL`] database).
+pub const COLL_OPLOG: &str = "oplog.rs";
+
+/// Name of the collection storing server version and identity documents.
+pub const COLL_SYSTEM_VERSION: &str = "system.version";
+
+/// Name of the collection storing the sharded cluster version and ID.
+pub const COLL_VERSION: &str = "version";
+
+/// Error code returned by MongoDB when a command is not supported by the server.
+pub const COMMAND_NOT_FOUND: i32 = 59;
+
 /// Name of the database to run admin commands against (also known as the admin database).
 pub const DB_ADMIN: &str = "admin";
 
+/// Name of the database with sharded cluster metadata (also known as the config database).
+pub const DB_CONFIG: &str = "config";
+
 /// Name of the database with local state on (also known as the local database).
 pub const DB_LOCAL: &str = "local";
 
 /// Parameter to the [`CMD_GET_PARAMETER`] command for retrieving the current FCV.
 pub const FEATURE_COMPATIBILITY_VERSION: &str = "featureCompatibilityVersion";
 
+/// Value of the `msg` field returned by [`CMD_HELLO`] when connected to a mongos.
+pub const HELLO_MSG_MONGOS: &str = "isdbgrid";
+
+/// Error code returned by MongoDB when the node is not running with replication enabled.
+pub const NO_REPLICATION_ENABLED: i32 = 76;
+
 /// Error code returned by MongoDB when the Replica Set is not initialised no the node.
 pub const REPL_SET_NOT_INITIALISED: i32 = 94;
 
+/// ID of the document in [`COLL_SYSTEM_VERSION`] that identifies shard members.
+pub const SHARD_IDENTITY: &str = "shardIdentity";
+
 /// Possible states of a MongoDB replica set member.
 ///
 /// <https://www.mongodb.com/docs/manual/reference/replica-states/>
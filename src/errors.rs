@@ -51,6 +51,18 @@ impl From<i32> for MemberStateParseError {
 /// Possible errors while gathering node information.
 #[derive(Debug, thiserror::Error)]
 pub enum MongoInfoError {
+    /// The sharded cluster version document does not include a cluster ID.
+    #[error("the sharded cluster version document does not include a cluster ID")]
+    ClusterIdNotFound,
+
+    /// Lookup of the sharded cluster ID failed.
+    #[error("lookup of the sharded cluster ID failed")]
+    ClusterIdUnknown,
+
+    /// Get command line options command failed.
+    #[error("get command line options command failed")]
+    CmdLineOptsUnknown,
+
     /// Output of feature compatibility version command does not include a version.
     #[error("output of feature compatibility version command does not include a version")]
     FeatCompatVerNotSet,
@@ -59,6 +71,26 @@ pub enum MongoInfoError {
     #[error("get feature compatibility version command failed")]
     FeatCompatVerUnknown,
 
+    /// Shard in the output of the list shards command is invalid.
+    #[error("shard in the output of the list shards command is invalid")]
+    ListShardsInvalidShard,
+
+    /// Output of the list shards command does not include a shards list.
+    #[error("output of the list shards command does not include a shards list")]
+    ListShardsNoShards,
+
+    /// List shards command failed.
+    #[error("list shards command failed")]
+    ListShardsUnknown,
+
+    /// Lookup of the first and last oplog entries failed.
+    #[error("lookup of the first and last oplog entries failed")]
+    OplogEntriesUnknown,
+
+    /// Oplog entry does not include a valid timestamp.
+    #[error("oplog entry does not include a valid timestamp")]
+    OplogEntryInvalid,
+
     /// Output of the oplog collection stats command does not include a collection size.
     #[error("output of the oplog collection stats command does not include a collection size")]
     OplogStatsNoSize,
@@ -67,6 +99,14 @@ pub enum MongoInfoError {
     #[error("get oplog collection statistics command failed")]
     OplogStatsUnknown,
 
+    /// Member in the output of the replica set status command is invalid.
+    #[error("member in the output of the replica set status command is invalid")]
+    ReplicaSetStatusInvalidMember,
+
+    /// Optime of a member in the output of the replica set status command is invalid.
+    #[error("optime of a member in the output of the replica set status command is invalid")]
+    ReplicaSetStatusInvalidOpTime,
+
     /// Self member in the output of the replica set status command is invalid.
     #[error("self member in the output of the replica set status command is invalid")]
     ReplicaSetStatusInvalidSelf,
@@ -86,4 +126,24 @@ pub enum MongoInfoError {
     /// Get replica set status command failed.
     #[error("get replica set status command failed")]
     ReplicaSetStatusUnknown,
+
+    /// Output of the server status command does not include the server host.
+    #[error("output of the server status command does not include the server host")]
+    ServerStatusNoHost,
+
+    /// Output of the server status command does not include the server uptime.
+    #[error("output of the server status command does not include the server uptime")]
+    ServerStatusNoUptime,
+
+    /// Get server status command failed.
+    #[error("get server status command failed")]
+    ServerStatusUnknown,
+
+    /// The shard identity document is missing required attributes.
+    #[error("the shard identity document is missing required attributes")]
+    ShardIdentityInvalid,
+
+    /// Lookup of the shard identity document failed.
+    #[error("lookup of the shard identity document failed")]
+    ShardIdentityUnknown,
 }
@@ -10,11 +10,14 @@ mod conf;
 mod constants;
 mod errors;
 mod metrics;
+mod mongos;
 mod replicaset;
+mod standalone;
 mod trace;
 
 use self::cli::Cli;
 use self::cli::Mode;
+use self::replicaset::ReplicaSetMode;
 
 /// ID of the agent release in sentry recommanded format.
 const RELEASE_ID: &str = concat!(env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"));
@@ -31,6 +34,10 @@ pub fn run() -> Result<()> {
     // Parse command line options and decide what to run.
     let args = Cli::parse();
     match args.mode {
-        Mode::ReplicaSet => self::replicaset::run(args),
+        Mode::Mongos => self::mongos::run(args),
+        Mode::ConfigServer => self::replicaset::run(args, ReplicaSetMode::ConfigServer),
+        Mode::ReplicaSet => self::replicaset::run(args, ReplicaSetMode::ReplicaSet),
+        Mode::Shard => self::replicaset::run(args, ReplicaSetMode::Shard),
+        Mode::Standalone => self::standalone::run(args),
     }
 }
@@ -13,6 +13,11 @@ use replisdk::agent::framework::InitialiseHookArgs;
 
 use crate::conf::Conf;
 
+mod oplog;
+mod server;
+
+pub use self::oplog::RegisterOplog;
+
 /// Duration (in seconds) of MongoDB operations issued to the server.
 pub static MONGODB_OPS_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
     HistogramVec::new(
@@ -39,8 +44,20 @@ pub static MONGODB_OPS_ERR: Lazy<CounterVec> = Lazy::new(|| {
     .expect("failed to initialise MONGODB_OPS_ERR counter")
 });
 
-/// Initialisation hook to register agent metrics.
-pub struct Register;
+/// Initialisation hook to register agent metrics common to all modes.
+///
+/// Modes that manage replica set members also sample member metrics, when enabled.
+#[derive(Default)]
+pub struct Register {
+    members: bool,
+}
+
+impl Register {
+    /// Register metrics for modes managing replica set members.
+    pub fn for_replica_set() -> Register {
+        Register { members: true }
+    }
+}
 
 #[async_trait::async_trait]
 impl InitialiseHook for Register {
@@ -53,6 +70,17 @@ impl InitialiseHook for Register {
         for collector in collectors {
             args.telemetry.metrics.register(collector)?;
         }
+
+        // Sample server metrics only if enabled (an interval of zero disables sampling).
+        let interval = args.conf.custom.server_metrics_interval;
+        if let Some(interval) = interval.filter(|interval| *interval > 0) {
+            for collector in self::server::collectors(self.members) {
+                args.telemetry.metrics.register(collector)?;
+            }
+            let interval = std::time::Duration::from_secs(interval);
+            let logger = args.telemetry.logger.clone();
+            self::server::start(interval, logger, self.members);
+        }
         Ok(())
     }
 }
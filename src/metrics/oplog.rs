@@ -0,0 +1,62 @@
+//! Oplog metrics for nodes running as Replica Set members.
+//!
+//! Only Replica Set members have an oplog so these metrics are registered in those modes only.
+//! Values are refreshed by a background task independently of node information requests.
+use std::time::Duration;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use prometheus::Gauge;
+use slog::Logger;
+
+use replisdk::agent::framework::InitialiseHook;
+use replisdk::agent::framework::InitialiseHookArgs;
+
+use crate::conf::Conf;
+
+/// Interval between refreshes of the oplog window metric.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time range (in seconds) covered by the entries in the node's oplog.
+pub static MONGODB_OPLOG_WINDOW: Lazy<Gauge> = Lazy::new(|| {
+    Gauge::new(
+        "repliagent_mongodb_oplog_window_seconds",
+        "Time range (in seconds) covered by the entries in the node's oplog",
+    )
+    .expect("failed to initialise MONGODB_OPLOG_WINDOW gauge")
+});
+
+/// Initialisation hook to register and refresh oplog metrics.
+pub struct RegisterOplog;
+
+#[async_trait::async_trait]
+impl InitialiseHook for RegisterOplog {
+    type Conf = Conf;
+    async fn initialise<'a>(&self, args: &InitialiseHookArgs<'a, Self::Conf>) -> Result<()> {
+        args.telemetry
+            .metrics
+            .register(Box::new(MONGODB_OPLOG_WINDOW.clone()))?;
+        start(args.telemetry.logger.clone());
+        Ok(())
+    }
+}
+
+/// Start a background task refreshing oplog metrics.
+fn start(logger: Logger) {
+    tokio::spawn(async move {
+        let client = crate::client::global();
+        let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            // Arbiters have no oplog so there is nothing to report for them.
+            match crate::replicaset::info::oplog::window(&client).await {
+                Ok(Some(window)) => MONGODB_OPLOG_WINDOW.set(f64::from(window.seconds())),
+                Ok(None) => MONGODB_OPLOG_WINDOW.set(0.0),
+                Err(error) => {
+                    slog::debug!(logger, "Unable to refresh oplog window metric"; "error" => %error);
+                }
+            }
+        }
+    });
+}
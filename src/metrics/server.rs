@@ -0,0 +1,442 @@
+//! Optional collection of MongoDB server metrics sampled by the agent.
+//!
+//! When enabled, the agent periodically runs `serverStatus` (and `replSetGetStatus` on
+//! replica set members) and exposes selected values on the agent's metrics endpoint.
+//! Cumulative server values (such as operation counters) are exposed as Prometheus counters
+//! incremented by the difference between samples, all other values are exposed as gauges.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use mongodb::Client;
+use once_cell::sync::Lazy;
+use prometheus::core::Collector;
+use prometheus::CounterVec;
+use prometheus::GaugeVec;
+use prometheus::Opts;
+use slog::Logger;
+
+use crate::constants::MemberState;
+use crate::replicaset::info::optime::OpTime;
+
+/// Number of operations executed by the server, by operation type.
+pub static SERVER_OPCOUNTERS: Lazy<CounterVec> = Lazy::new(|| {
+    CounterVec::new(
+        Opts::new(
+            "repliagent_mongodb_server_opcounters",
+            "Number of operations executed by the server, by operation type",
+        ),
+        &["op"],
+    )
+    .expect("failed to initialise SERVER_OPCOUNTERS counter")
+});
+
+/// Number of incoming connections to the server, by connection state.
+pub static SERVER_CONNECTIONS: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "repliagent_mongodb_server_connections",
+            "Number of incoming connections to the server, by connection state",
+        ),
+        &["state"],
+    )
+    .expect("failed to initialise SERVER_CONNECTIONS gauge")
+});
+
+/// Network traffic handled by the server, by metric.
+pub static SERVER_NETWORK: Lazy<CounterVec> = Lazy::new(|| {
+    CounterVec::new(
+        Opts::new(
+            "repliagent_mongodb_server_network",
+            "Network traffic handled by the server (bytes in, bytes out and requests)",
+        ),
+        &["metric"],
+    )
+    .expect("failed to initialise SERVER_NETWORK counter")
+});
+
+/// WiredTiger cache size and usage in bytes, by metric.
+pub static SERVER_WT_CACHE_BYTES: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "repliagent_mongodb_server_wiredtiger_cache_bytes",
+            "WiredTiger cache size and usage in bytes",
+        ),
+        &["metric"],
+    )
+    .expect("failed to initialise SERVER_WT_CACHE_BYTES gauge")
+});
+
+/// Number of assertions raised by the server, by assertion type.
+pub static SERVER_ASSERTS: Lazy<CounterVec> = Lazy::new(|| {
+    CounterVec::new(
+        Opts::new(
+            "repliagent_mongodb_server_asserts",
+            "Number of assertions raised by the server, by assertion type",
+        ),
+        &["type"],
+    )
+    .expect("failed to initialise SERVER_ASSERTS counter")
+});
+
+/// State of the buffer of oplog entries fetched from the sync source, by metric.
+pub static SERVER_REPL_BUFFER: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "repliagent_mongodb_server_repl_buffer",
+            "State of the buffer of oplog entries fetched from the sync source",
+        ),
+        &["metric"],
+    )
+    .expect("failed to initialise SERVER_REPL_BUFFER gauge")
+});
+
+/// Replica set state of each member, as seen by the node.
+pub static MEMBER_STATE: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "repliagent_mongodb_member_state",
+            "Replica set state of each member, as seen by the node",
+        ),
+        &["member"],
+    )
+    .expect("failed to initialise MEMBER_STATE gauge")
+});
+
+/// Health of each replica set member (1 for reachable, 0 otherwise), as seen by the node.
+pub static MEMBER_HEALTH: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "repliagent_mongodb_member_health",
+            "Health of each replica set member (1 for reachable, 0 otherwise)",
+        ),
+        &["member"],
+    )
+    .expect("failed to initialise MEMBER_HEALTH gauge")
+});
+
+/// Replication lag (in seconds) of each replica set member behind the primary.
+pub static MEMBER_LAG: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "repliagent_mongodb_member_lag_seconds",
+            "Replication lag (in seconds) of each replica set member behind the primary",
+        ),
+        &["member"],
+    )
+    .expect("failed to initialise MEMBER_LAG gauge")
+});
+
+/// Fields sampled from `serverStatus` sections, with the labels they are reported as.
+const OPCOUNTERS: [&str; 6] = ["insert", "query", "update", "delete", "getmore", "command"];
+const ASSERTS: [&str; 5] = ["regular", "warning", "msg", "user", "rollovers"];
+const NETWORK: [(&str, &str); 3] = [
+    ("bytesIn", "bytes_in"),
+    ("bytesOut", "bytes_out"),
+    ("numRequests", "requests"),
+];
+const WT_CACHE: [(&str, &str); 3] = [
+    ("maximum bytes configured", "configured"),
+    ("bytes currently in the cache", "used"),
+    ("tracked dirty bytes in the cache", "dirty"),
+];
+const REPL_BUFFER: [(&str, &str); 3] = [
+    ("count", "count"),
+    ("sizeBytes", "size_bytes"),
+    ("maxSizeBytes", "max_size_bytes"),
+];
+
+/// Collectors for server metrics to register with the agent metrics registry.
+///
+/// Replica set member metrics are included only if `members` is set.
+pub fn collectors(members: bool) -> Vec<Box<dyn Collector>> {
+    let mut collectors: Vec<Box<dyn Collector>> = vec![
+        Box::new(SERVER_ASSERTS.clone()),
+        Box::new(SERVER_CONNECTIONS.clone()),
+        Box::new(SERVER_NETWORK.clone()),
+        Box::new(SERVER_OPCOUNTERS.clone()),
+        Box::new(SERVER_REPL_BUFFER.clone()),
+        Box::new(SERVER_WT_CACHE_BYTES.clone()),
+    ];
+    if members {
+        collectors.push(Box::new(MEMBER_HEALTH.clone()));
+        collectors.push(Box::new(MEMBER_LAG.clone()));
+        collectors.push(Box::new(MEMBER_STATE.clone()));
+    }
+    collectors
+}
+
+/// Start a background task sampling server metrics at the given interval.
+///
+/// Replica set member metrics are sampled only if `members` is set,
+/// since `replSetGetStatus` always fails on nodes that are not replica set members.
+pub fn start(interval: Duration, logger: Logger, members: bool) {
+    tokio::spawn(async move {
+        let client = crate::client::global();
+        let mut sampler = Sampler {
+            last: HashMap::new(),
+            members,
+        };
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            sampler.sample(&client, &logger).await;
+        }
+    });
+}
+
+/// Track cumulative server values across samples to increment counters by.
+#[derive(Debug, Default)]
+struct Sampler {
+    last: HashMap<String, f64>,
+
+    /// Sample replica set member metrics too.
+    members: bool,
+}
+
+impl Sampler {
+    /// Sample server metrics once.
+    async fn sample(&mut self, client: &Client, logger: &Logger) {
+        match crate::client::admin::server_status(client).await {
+            Ok(status) => self.observe_server_status(&status),
+            Err(error) => {
+                slog::debug!(logger, "Unable to sample server status metrics"; "error" => %error);
+            }
+        }
+
+        // Members no longer in the replica set are not reported.
+        if !self.members {
+            return;
+        }
+        match crate::client::admin::replica_set_status(client).await {
+            Ok(status) => observe_replica_set_status(&status),
+            Err(error) => {
+                reset_members();
+                slog::debug!(logger, "Unable to sample replica set metrics"; "error" => %error);
+            }
+        }
+    }
+
+    /// Increment needed to move a counter from the last sample of a value to the current one.
+    ///
+    /// Server values reset when the process restarts, in which case the new value is the delta.
+    fn delta(&mut self, key: String, value: f64) -> f64 {
+        let last = self.last.insert(key, value);
+        match last {
+            Some(last) if value >= last => value - last,
+            _ => value,
+        }
+    }
+
+    /// Increment a server counter to match the server value.
+    fn advance(&mut self, counter: &CounterVec, label: &str, value: f64) {
+        let key = format!("{}/{}", counter.desc()[0].fq_name, label);
+        let delta = self.delta(key, value);
+        counter.with_label_values(&[label]).inc_by(delta);
+    }
+
+    /// Update server metrics from the output of `serverStatus`.
+    fn observe_server_status(&mut self, status: &Document) {
+        if let Ok(opcounters) = status.get_document("opcounters") {
+            for op in OPCOUNTERS {
+                if let Some(value) = number(opcounters.get(op)) {
+                    self.advance(&SERVER_OPCOUNTERS, op, value);
+                }
+            }
+        }
+        if let Ok(asserts) = status.get_document("asserts") {
+            for kind in ASSERTS {
+                if let Some(value) = number(asserts.get(kind)) {
+                    self.advance(&SERVER_ASSERTS, kind, value);
+                }
+            }
+        }
+        if let Ok(network) = status.get_document("network") {
+            for (field, label) in NETWORK {
+                if let Some(value) = number(network.get(field)) {
+                    self.advance(&SERVER_NETWORK, label, value);
+                }
+            }
+        }
+        if let Ok(connections) = status.get_document("connections") {
+            for state in ["current", "available"] {
+                if let Some(value) = number(connections.get(state)) {
+                    SERVER_CONNECTIONS.with_label_values(&[state]).set(value);
+                }
+            }
+        }
+        let cache = status
+            .get_document("wiredTiger")
+            .and_then(|wt| wt.get_document("cache"));
+        if let Ok(cache) = cache {
+            for (field, label) in WT_CACHE {
+                if let Some(value) = number(cache.get(field)) {
+                    SERVER_WT_CACHE_BYTES.with_label_values(&[label]).set(value);
+                }
+            }
+        }
+        let buffer = status
+            .get_document("metrics")
+            .and_then(|metrics| metrics.get_document("repl"))
+            .and_then(|repl| repl.get_document("buffer"));
+        if let Ok(buffer) = buffer {
+            for (field, label) in REPL_BUFFER {
+                if let Some(value) = number(buffer.get(field)) {
+                    SERVER_REPL_BUFFER.with_label_values(&[label]).set(value);
+                }
+            }
+        }
+    }
+}
+
+/// Metrics about a replica set member extracted from `replSetGetStatus`.
+#[derive(Debug, PartialEq)]
+struct MemberSample {
+    health: f64,
+    host: String,
+    lag: Option<f64>,
+    state: f64,
+}
+
+/// Update replica set member metrics from the output of `replSetGetStatus`.
+///
+/// Samples are extracted before the previous values are cleared so that members
+/// are missing from the metrics for as short a time as possible.
+fn observe_replica_set_status(status: &Document) {
+    let samples = member_samples(status);
+    reset_members();
+    for member in samples {
+        let labels = [member.host.as_str()];
+        MEMBER_HEALTH.with_label_values(&labels).set(member.health);
+        MEMBER_STATE.with_label_values(&labels).set(member.state);
+        if let Some(lag) = member.lag {
+            MEMBER_LAG.with_label_values(&labels).set(lag);
+        }
+    }
+}
+
+/// Clear replica set member metrics.
+fn reset_members() {
+    MEMBER_HEALTH.reset();
+    MEMBER_LAG.reset();
+    MEMBER_STATE.reset();
+}
+
+/// Extract metrics for all replica set members in the output of `replSetGetStatus`.
+///
+/// Lag is only reported for healthy data-bearing members when the replica set has a primary.
+fn member_samples(status: &Document) -> Vec<MemberSample> {
+    let members: Vec<&Document> = status
+        .get_array("members")
+        .map(|members| members.iter().filter_map(Bson::as_document).collect())
+        .unwrap_or_default();
+    let primary = members
+        .iter()
+        .find(|member| member.get_i32("state") == Ok(MemberState::Primary as i32))
+        .and_then(|primary| OpTime::from_member(primary).ok().flatten());
+
+    let mut samples = Vec::new();
+    for member in members {
+        let host = match member.get_str("name") {
+            Err(_) => continue,
+            Ok(host) => host.to_string(),
+        };
+        let state = member
+            .get_i32("state")
+            .unwrap_or(MemberState::Unknown as i32);
+        let health = number(member.get("health")).unwrap_or(0.0);
+        let lag = match (&primary, OpTime::from_member(member).ok().flatten()) {
+            (Some(primary), Some(optime))
+                if health >= 1.0 && state != MemberState::Arbiter as i32 =>
+            {
+                Some(optime.lag_behind(primary).seconds as f64)
+            }
+            _ => None,
+        };
+        samples.push(MemberSample {
+            health,
+            host,
+            lag,
+            state: f64::from(state),
+        });
+    }
+    samples
+}
+
+/// Read a server status number as a float, regardless of the BSON type used.
+fn number(value: Option<&Bson>) -> Option<f64> {
+    match value {
+        Some(Bson::Int32(value)) => Some(f64::from(*value)),
+        Some(Bson::Int64(value)) => Some(*value as f64),
+        Some(Bson::Double(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+    use mongodb::bson::Timestamp;
+
+    use super::member_samples;
+    use super::MemberSample;
+    use super::Sampler;
+
+    #[test]
+    fn counter_delta() {
+        let mut sampler = Sampler::default();
+        assert_eq!(sampler.delta("ops".into(), 10.0), 10.0);
+        assert_eq!(sampler.delta("ops".into(), 15.0), 5.0);
+        // The server restarted so the counter reset.
+        assert_eq!(sampler.delta("ops".into(), 3.0), 3.0);
+    }
+
+    #[test]
+    fn members_sampled() {
+        let status = doc! {
+            "set": "rs0",
+            "members": [{
+                "_id": 0,
+                "name": "mongo-0:27017",
+                "health": 1.0,
+                "state": 1,
+                "optime": {"ts": Timestamp { time: 100, increment: 1 }, "t": 1_i64},
+            }, {
+                "_id": 1,
+                "name": "mongo-1:27017",
+                "health": 1.0,
+                "state": 2,
+                "optime": Timestamp { time: 95, increment: 3 },
+            }, {
+                "_id": 2,
+                "name": "mongo-2:27017",
+                "health": 0.0,
+                "state": 8,
+            }],
+        };
+        let samples = member_samples(&status);
+        let expected = vec![
+            MemberSample {
+                health: 1.0,
+                host: "mongo-0:27017".into(),
+                lag: Some(0.0),
+                state: 1.0,
+            },
+            MemberSample {
+                health: 1.0,
+                host: "mongo-1:27017".into(),
+                lag: Some(5.0),
+                state: 2.0,
+            },
+            MemberSample {
+                health: 0.0,
+                host: "mongo-2:27017".into(),
+                lag: None,
+                state: 8.0,
+            },
+        ];
+        assert_eq!(samples, expected);
+    }
+}
@@ -0,0 +1,38 @@
+//! Factory for MongosInfo instances.
+use anyhow::Result;
+
+use replisdk::agent::framework::detect_node_id;
+use replisdk::agent::framework::NodeInfoFactory;
+use replisdk::agent::framework::NodeInfoFactoryArgs;
+
+use super::MongosInfo;
+use crate::conf::Conf;
+
+/// Create instances of [`MongosInfo`] at the correct process initialisation time.
+pub struct MongosInfoFactory {}
+
+#[async_trait::async_trait]
+impl NodeInfoFactory for MongosInfoFactory {
+    type Conf = Conf;
+    type NodeInfo = MongosInfo;
+
+    async fn factory<'a>(&self, args: NodeInfoFactoryArgs<'a, Self::Conf>) -> Result<MongosInfo> {
+        // Grab identifiers to report from the API.
+        let node_id = detect_node_id(args.conf, &args.telemetry.logger).await?;
+
+        // Configure the store version detection strategies.
+        let client = crate::client::global();
+        let version = crate::replicaset::info::version::configure_strategies(
+            args.clone(),
+            "mongos",
+            &client,
+        )?;
+
+        // Create the MongosInfo instance.
+        Ok(MongosInfo {
+            client,
+            node_id,
+            version,
+        })
+    }
+}
@@ -0,0 +1,143 @@
+//! NodeInfo implementation for MongoDB routers.
+use std::future::IntoFuture;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Document;
+use mongodb::Client;
+use once_cell::sync::Lazy;
+use opentelemetry::trace::FutureExt;
+
+use replisdk::agent::framework::NodeInfo;
+use replisdk::agent::framework::StoreVersionChain;
+use replisdk::agent::framework::StoreVersionStrategy;
+use replisdk::agent::models::AttributesMap;
+use replisdk::agent::models::Node;
+use replisdk::agent::models::ShardsInfo;
+use replisdk::agent::models::StoreExtras;
+use replisdk::context::Context;
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureErrExt;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+mod factory;
+mod shards;
+mod status;
+
+pub use self::factory::MongosInfoFactory;
+
+use crate::constants::ATTRIBUTE_PREFIX;
+use crate::constants::CMD_LIST_SHARDS;
+use crate::constants::COLL_VERSION;
+use crate::constants::DB_ADMIN;
+use crate::constants::DB_CONFIG;
+use crate::errors::MongoInfoError;
+use crate::metrics::observe_mongodb_op;
+
+/// Store ID reported for nodes.
+const STORE_ID: &str = "mongo.sharded";
+
+/// Set of never-changing agent attributes to include in responses.
+static STATIC_ATTRIBUTES: Lazy<AttributesMap> = Lazy::new(|| {
+    let mut attributes = AttributesMap::new();
+    attributes.insert(format!("{}/mode", ATTRIBUTE_PREFIX), "mongos".into());
+    attributes
+});
+
+/// Gather MongoDB router information.
+#[derive(Clone, Debug)]
+pub struct MongosInfo {
+    client: Client,
+    node_id: String,
+    version: StoreVersionChain,
+}
+
+impl MongosInfo {
+    /// Return the factory for [`MongosInfo`] instances.
+    pub fn factory() -> MongosInfoFactory {
+        MongosInfoFactory {}
+    }
+}
+
+impl MongosInfo {
+    /// Lookup the ID of the sharded cluster from the config servers.
+    async fn cluster_id(&self) -> Result<String> {
+        let trace = crate::trace::mongodb_client_context(COLL_VERSION);
+        let (err_count, _timer) = observe_mongodb_op(COLL_VERSION);
+
+        let versions = self
+            .client
+            .database(DB_CONFIG)
+            .collection::<Document>(COLL_VERSION);
+
+        // Wrap the command to be traced into an anonymous future to decorate.
+        let observed = async {
+            let version = versions
+                .find_one(Document::new())
+                .await
+                .context(MongoInfoError::ClusterIdUnknown)?
+                .ok_or(MongoInfoError::ClusterIdNotFound)?;
+            let cluster_id = version
+                .get_object_id("clusterId")
+                .context(MongoInfoError::ClusterIdNotFound)?
+                .to_hex();
+            Ok(cluster_id)
+        };
+
+        // Decorate the operation once for all return clauses and execute.
+        TraceFutureErrExt::trace_on_err_with_status(observed)
+            .count_on_err(err_count)
+            .with_context(trace)
+            .await
+    }
+
+    /// List shards in the sharded cluster.
+    async fn list_shards(&self) -> Result<Document> {
+        let trace = crate::trace::mongodb_client_context(CMD_LIST_SHARDS);
+        let (err_count, _timer) = observe_mongodb_op(CMD_LIST_SHARDS);
+
+        let admin = self.client.database(DB_ADMIN);
+        let command = mongodb::bson::doc! {CMD_LIST_SHARDS: 1};
+        let list = admin
+            .run_command(command)
+            .into_future()
+            .count_on_err(err_count)
+            .trace_on_err_with_status()
+            .with_context(trace)
+            .await
+            .context(MongoInfoError::ListShardsUnknown)?;
+        Ok(list)
+    }
+}
+
+#[async_trait::async_trait]
+impl NodeInfo for MongosInfo {
+    async fn node_info(&self, context: &Context) -> Result<Node> {
+        let node_status = self::status::get(&self.client, &context.logger).await?;
+        let store_version = self.version.version(context).await?;
+        let node = Node {
+            address: crate::replicaset::info::address::detect()?,
+            agent_version: crate::AGENT_VERSION.clone(),
+            attributes: STATIC_ATTRIBUTES.clone(),
+            node_id: self.node_id.clone(),
+            node_status,
+            store_id: STORE_ID.into(),
+            store_version,
+        };
+        Ok(node)
+    }
+
+    async fn shards(&self, _: &Context) -> Result<ShardsInfo> {
+        let list = self.list_shards().await?;
+        let shards = self::shards::shards(list)?;
+        Ok(ShardsInfo { shards })
+    }
+
+    async fn store_info(&self, _: &Context) -> Result<StoreExtras> {
+        let cluster_id = self.cluster_id().await?;
+        Ok(StoreExtras {
+            cluster_id,
+            attributes: AttributesMap::new(),
+        })
+    }
+}
@@ -0,0 +1,71 @@
+//! Model the sharded cluster shards list into [`Shard`]s.
+use anyhow::Context;
+use anyhow::Result;
+use mongodb::bson::Document;
+
+use replisdk::agent::models::Shard;
+use replisdk::agent::models::ShardCommitOffset;
+use replisdk::agent::models::ShardRole;
+
+use crate::errors::MongoInfoError;
+
+/// Role reported by routers for all shards in the cluster.
+const ROLE_ROUTER: &str = "ROUTER";
+
+/// Model the output of the `listShards` command into a list of [`Shard`]s.
+///
+/// Routers do not store data so commit offsets are not tracked and always reported as zero.
+pub fn shards(list: Document) -> Result<Vec<Shard>> {
+    let shards = list
+        .get_array("shards")
+        .context(MongoInfoError::ListShardsNoShards)?;
+    shards
+        .iter()
+        .map(|shard| {
+            let shard_id = shard
+                .as_document()
+                .ok_or(MongoInfoError::ListShardsInvalidShard)?
+                .get_str("_id")
+                .context(MongoInfoError::ListShardsInvalidShard)?
+                .to_string();
+            Ok(Shard {
+                commit_offset: ShardCommitOffset::milliseconds(0),
+                lag: None,
+                role: ShardRole::Other(ROLE_ROUTER.into()),
+                shard_id,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use replisdk::agent::models::ShardRole;
+
+    use super::shards;
+
+    #[test]
+    fn shards_listed() {
+        let list = mongodb::bson::doc! {
+            "shards": [
+                {"_id": "shard01", "host": "shard01/a:27018,b:27018", "state": 1},
+                {"_id": "shard02", "host": "shard02/c:27018,d:27018", "state": 1},
+            ],
+            "ok": 1,
+        };
+        let shards = shards(list).unwrap();
+        let ids: Vec<_> = shards.iter().map(|shard| shard.shard_id.as_str()).collect();
+        assert_eq!(ids, vec!["shard01", "shard02"]);
+        assert_eq!(shards[0].role, ShardRole::Other("ROUTER".into()));
+    }
+
+    #[test]
+    fn shards_missing() {
+        let list = mongodb::bson::doc! {"ok": 1};
+        let error = shards(list).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<crate::errors::MongoInfoError>(),
+            Some(crate::errors::MongoInfoError::ListShardsNoShards)
+        ));
+    }
+}
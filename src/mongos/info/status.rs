@@ -0,0 +1,43 @@
+//! Detect the node status for MongoDB routers.
+use anyhow::Result;
+use mongodb::error::Error;
+use mongodb::Client;
+use slog::Logger;
+
+use replisdk::agent::models::NodeStatus;
+
+use crate::constants::HELLO_MSG_MONGOS;
+
+/// Get the current [`NodeStatus`] of the router based on the `ping` and `hello` commands.
+pub async fn get(client: &Client, logger: &Logger) -> Result<NodeStatus> {
+    if let Err(error) = crate::client::admin::ping(client).await {
+        slog::debug!(logger, "Error executing ping"; "server_error" => %error);
+        return Ok(status_for_error(error));
+    }
+    let hello = match crate::client::admin::hello(client).await {
+        Ok(hello) => hello,
+        Err(error) => {
+            slog::debug!(logger, "Error executing hello"; "server_error" => %error);
+            return Ok(status_for_error(error));
+        }
+    };
+
+    // Make sure the agent is connected to a router and not some other node.
+    if !matches!(hello.get_str("msg"), Ok(HELLO_MSG_MONGOS)) {
+        let status = NodeStatus::Unknown("the agent is not connected to a mongos router".into());
+        return Ok(status);
+    }
+    Ok(NodeStatus::Healthy)
+}
+
+/// Determine the [`NodeStatus`] based on the error response to router commands.
+fn status_for_error(error: Error) -> NodeStatus {
+    // Check for connection related errors, suggesting the store process is down.
+    if crate::client::admin::connection_failed(&error) {
+        return NodeStatus::Unavailable;
+    }
+
+    // Consider all other errors unknown.
+    let message = error.to_string();
+    NodeStatus::Unknown(message)
+}
@@ -0,0 +1,56 @@
+//! Agent for MongoDB routers (mongos) in Sharded clusters.
+use anyhow::Result;
+
+use replisdk::agent::framework::Agent;
+use replisdk::agent::framework::AgentConf;
+use replisdk::agent::framework::AgentOptions;
+use replisdk::runtime::telemetry::TelemetryOptions;
+
+use crate::conf::Conf;
+use crate::Cli;
+
+mod info;
+
+/// Explicitly typed Agent builder for MongoDB router agents.
+///
+/// Having this explicit type can defined decorator functions to set up the agent
+/// and surface type-related issues quickly and more clearly.
+type MongosAgent = Agent<Conf, info::MongosInfoFactory>;
+
+/// Configuration of MongoDB router agents.
+type MongosConf = AgentConf<Conf>;
+
+/// Run a Replicante Agent for MongoDB routers in Sharded clusters.
+pub fn run(args: Cli) -> Result<()> {
+    let mut conf = crate::conf::load(&args.config, MongosConf::default())?;
+    crate::conf::apply_overrides(&mut conf.custom)?;
+    conf.runtime
+        .tokio
+        .clone()
+        .into_runtime()
+        .expect("failed configuration of tokio runtime")
+        .block_on(async_run(args, conf))
+}
+
+async fn async_run(_args: Cli, conf: MongosConf) -> Result<()> {
+    let options = AgentOptions {
+        requests_metrics_prefix: "repliagent",
+    };
+    let telemetry = TelemetryOptions::for_sentry_release(crate::RELEASE_ID)
+        .for_app(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        .finish();
+
+    // Configure the agent process using the `Agent` builder.
+    // Routers hold no data so no cluster management actions are available.
+    let agent = MongosAgent::build()
+        .configure(conf)
+        .options(options)
+        .telemetry_options(telemetry)
+        .node_info(info::MongosInfo::factory())
+        .initialise_with(crate::client::Initialise)
+        .initialise_with(crate::metrics::Register::default())
+        .register_actions(replisdk::agent::framework::actions::wellknown::test::all());
+
+    // Run the agent until error or shutdown.
+    agent.run().await
+}
@@ -1,8 +1,11 @@
 //! Agent action to add a node to the current Replica Set.
 //!
-//! The action will reconfigure the replica set to add a node with [`replSetInitiate`].
+//! The action will reconfigure the replica set to add a node with [`replSetReconfig`].
 //! If the current node is not the Replica Set primary the action will fail.
 //!
+//! If the node is already a member of the replica set (with the requested `_id`, if any)
+//! the action completes without changes to the replica set so retries are safe.
+//!
 //! ## Arguments
 //!
 //! Arguments are required unless otherwise noted.
@@ -11,14 +14,25 @@
 //!
 //! - `id` [OPTIONAL]: Index to use for the new node `_id` attribute.
 //!   If not set, largest integer not currently in use is assigned.
+//!   The action fails if the `_id` is already in use by a different node.
 //! - `host`: Value of the new node for the `host` attribute.
+//! - `buildIndexes` [OPTIONAL]: Build indexes on the new node (requires priority 0 if `false`).
+//! - `hidden` [OPTIONAL]: Hide the new node from clients (requires priority 0).
+//! - `priority` [OPTIONAL]: Election priority of the new node (0 to 1000).
+//! - `secondaryDelaySecs` [OPTIONAL]: Seconds the new node lags behind the primary.
+//!   Also accepted as `slaveDelay` and sent to the server with the name it supports.
+//! - `tags` [OPTIONAL]: Map of tags to set on the new node.
+//! - `votes` [OPTIONAL]: Number of votes the new node has (0 or 1).
+//!
+//! Member options are validated before the replica set is reconfigured.
+//!
+//! The replica set is reconfigured in safe steps (see [`Reconfiguration`]) and the action
+//! remains running until the new configuration is committed.
 //!
 //! [`replSetReconfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetReconfig/
-use std::future::IntoFuture;
-
 use anyhow::Context as AnyContext;
 use anyhow::Result;
-use opentelemetry::trace::FutureExt;
+use mongodb::bson::Document;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -28,17 +42,11 @@ use replisdk::agent::framework::actions::ActionMetadata;
 use replisdk::agent::models::ActionExecution;
 use replisdk::agent::models::ActionExecutionPhase;
 use replisdk::context::Context;
-use replisdk::utils::metrics::CountFutureErrExt;
-use replisdk::utils::trace::TraceFutureStdErrExt;
-
-use crate::constants::CMD_REPL_SET_GET_CONFIG;
-use crate::constants::CMD_REPL_SET_RECONFIG;
-use crate::constants::DB_ADMIN;
-use crate::metrics::observe_mongodb_op;
 
-const RS_ATTR_MEMBER_ID: &str = "_id";
-const RS_ATTR_MEMBERS: &str = "members";
-const RS_ATTR_VERSION: &str = "version";
+use crate::replicaset::actions::config;
+use crate::replicaset::actions::member;
+use crate::replicaset::actions::member::MemberOptions;
+use crate::replicaset::actions::reconfig::Reconfiguration;
 
 /// Add a node to the Replica Set cluster.
 #[derive(Debug)]
@@ -58,73 +66,46 @@ impl ActionHandler for Add {
             serde_json::from_value(action.args.clone()).context(AddError::InvalidArgs)?;
         let client = crate::client::global();
 
-        // Get current RS configuration.
-        let admin = client.database(DB_ADMIN);
-        let command = mongodb::bson::doc! {CMD_REPL_SET_GET_CONFIG: 1};
-        let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_GET_CONFIG);
-        let (err_count, timer) = observe_mongodb_op(CMD_REPL_SET_GET_CONFIG);
-        let rs = admin
-            .run_command(command)
-            .into_future()
-            .count_on_err(err_count)
-            .trace_on_err_with_status()
-            .with_context(trace)
+        // Get current RS configuration and resume in-progress reconfigurations.
+        let reconfig = Reconfiguration::load(&client, action)
             .await
-            .context(AddError::Failed)?
-            .remove("config")
-            .ok_or_else(|| anyhow::anyhow!("server did not return replica set configuration"))
-            .context(AddError::RsConf)?;
-        drop(timer);
-        let mut rs = match rs {
-            mongodb::bson::Bson::Document(rs) => rs,
-            _ => {
-                let error = anyhow::anyhow!("server returned invalid type for rs configuration");
-                anyhow::bail!(error.context(AddError::RsConf))
-            }
-        };
-        let members = rs
-            .get_array_mut(RS_ATTR_MEMBERS)
-            .context(AddError::RsAttr(RS_ATTR_MEMBERS))?;
+            .context(AddError::Failed)?;
+        if reconfig.in_progress() {
+            return reconfig.resume(context).await.context(AddError::Failed);
+        }
+        let mut rs = reconfig.config().clone();
 
         // Build new node document.
-        let mut nid = 0;
-        for member in members.iter() {
-            let id = member
-                .as_document()
-                .ok_or_else(|| anyhow::anyhow!("elements in members array must be an object"))
-                .context(AddError::RsConf)?
-                .get_i32(RS_ATTR_MEMBER_ID)
-                .context(AddError::RsAttr(RS_ATTR_MEMBER_ID))?;
-            if id > nid {
-                nid = id;
+        let nid = match new_member_id(&rs, &args.host, args.id)? {
+            Some(nid) => nid,
+            None => {
+                slog::info!(
+                    context.logger, "Node is already a member of the replica set";
+                    "host" => &args.host, "id" => ?args.id,
+                );
+                return Ok(Changes::to(ActionExecutionPhase::Done));
             }
-        }
-        let node = mongodb::bson::doc! {
-            "_id": nid + 1,
+        };
+        let build_info = crate::client::admin::build_info(&client)
+            .await
+            .context(AddError::Failed)?;
+        let major_version = member::major_version(&build_info)?;
+        let mut node = mongodb::bson::doc! {
+            "_id": nid,
             "host": args.host,
         };
+        args.options.apply(&mut node, major_version);
+        member::validate(&node).context(AddError::InvalidArgs)?;
 
         // Reconfigure the replica set.
         slog::info!(context.logger, "Adding node to replica set"; "node" => %node);
-        members.push(node.into());
-        let version = rs
-            .get_i32_mut(RS_ATTR_VERSION)
-            .context(AddError::RsAttr(RS_ATTR_VERSION))?;
-        *version += 1;
-
-        let command = mongodb::bson::doc! {CMD_REPL_SET_RECONFIG: rs};
-        let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_RECONFIG);
-        let (err_count, _timer) = observe_mongodb_op(CMD_REPL_SET_RECONFIG);
-        admin
-            .run_command(command)
-            .into_future()
-            .count_on_err(err_count)
-            .trace_on_err_with_status()
-            .with_context(trace)
+        config::members_mut(&mut rs)?.push(node.into());
+        member::validate_voters(&rs).context(AddError::InvalidArgs)?;
+        let members = config::members(&rs)?.into_iter().cloned().collect();
+        reconfig
+            .start(context, members)
             .await
-            .context(AddError::Failed)?;
-        let changes = Changes::to(ActionExecutionPhase::Done);
-        Ok(changes)
+            .context(AddError::Failed)
     }
 }
 
@@ -140,6 +121,10 @@ pub struct AddArgs {
     /// Value of the new node for the `host` attribute.
     #[serde(alias = "node")]
     pub host: String,
+
+    /// Optional attributes of the new node.
+    #[serde(flatten)]
+    pub options: MemberOptions,
 }
 
 /// Errors encountered while adding the new node.
@@ -149,16 +134,103 @@ pub enum AddError {
     #[error("unable to add node to replica set")]
     Failed,
 
+    /// The host is already a member of the replica set with a different `_id`.
+    #[error("the host '{host}' is already a member of the replica set with _id {id}")]
+    HostInUse { host: String, id: i32 },
+
+    /// The requested `_id` is already in use by a different member.
+    #[error("the requested _id {id} is already in use by member '{host}'")]
+    IdInUse { host: String, id: i32 },
+
     /// Arguments provided to the [`Add`] action are not valid.
     #[error("arguments provided to the add action are not valid")]
     InvalidArgs,
+}
+
+/// Determine the `_id` of the new member, checking for conflicts with existing members.
+///
+/// Returns `None` if the node is already a member of the replica set.
+pub(super) fn new_member_id(rs: &Document, new_host: &str, id: Option<u32>) -> Result<Option<i32>> {
+    let requested = id
+        .map(i32::try_from)
+        .transpose()
+        .context(AddError::InvalidArgs)?;
+
+    let mut max_id = None;
+    for member in config::members(rs)? {
+        let id = config::member_id(member)?;
+        let host = config::member_host(member)?;
+        max_id = max_id.max(Some(id));
+
+        // Check the host and requested ID for conflicts with existing members.
+        if host == new_host {
+            if requested.is_none() || requested == Some(id) {
+                return Ok(None);
+            }
+            anyhow::bail!(AddError::HostInUse {
+                host: host.to_string(),
+                id,
+            });
+        }
+        if requested == Some(id) {
+            anyhow::bail!(AddError::IdInUse {
+                host: host.to_string(),
+                id,
+            });
+        }
+    }
+
+    let nid = requested.unwrap_or_else(|| max_id.map(|id| id + 1).unwrap_or(0));
+    Ok(Some(nid))
+}
 
-    /// Attribute is missing on has unexpected type.
-    #[error("attribute '{0}' is missing on has unexpected type")]
-    // (attribute,)
-    RsAttr(&'static str),
+#[cfg(test)]
+mod tests {
+    use super::new_member_id;
+    use super::AddError;
+    use crate::replicaset::actions::fixtures::config;
+    use crate::replicaset::actions::fixtures::member;
+
+    #[test]
+    fn next_free_id() {
+        let rs = config(2, vec![member(0), member(3)]);
+        let nid = new_member_id(&rs, "mongo-2:27017", None).unwrap();
+        assert_eq!(nid, Some(4));
+    }
+
+    #[test]
+    fn requested_id() {
+        let rs = config(2, vec![member(0), member(3)]);
+        let nid = new_member_id(&rs, "mongo-2:27017", Some(1)).unwrap();
+        assert_eq!(nid, Some(1));
+    }
+
+    #[test]
+    fn requested_id_in_use() {
+        let rs = config(2, vec![member(0), member(3)]);
+        let error = new_member_id(&rs, "mongo-2:27017", Some(3)).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(AddError::IdInUse { id: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn host_in_use() {
+        let rs = config(2, vec![member(0), member(3)]);
+        let error = new_member_id(&rs, "mongo-3:27017", Some(1)).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(AddError::HostInUse { id: 3, .. })
+        ));
+    }
 
-    /// Invalid replica set configuration.
-    #[error("invalid replica set configuration")]
-    RsConf,
+    #[test]
+    fn already_member() {
+        let rs = config(2, vec![member(0), member(3)]);
+        let nid = new_member_id(&rs, "mongo-3:27017", Some(3)).unwrap();
+        assert_eq!(nid, None);
+        let nid = new_member_id(&rs, "mongo-3:27017", None).unwrap();
+        assert_eq!(nid, None);
+    }
 }
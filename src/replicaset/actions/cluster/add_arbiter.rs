@@ -0,0 +1,251 @@
+//! Agent action to add an arbiter to the current Replica Set.
+//!
+//! The action will reconfigure the replica set to add an arbiter with [`replSetReconfig`].
+//! If the current node is not the Replica Set primary the action will fail.
+//!
+//! Starting with MongoDB 5.0 the server refuses reconfigurations that change the implicit
+//! default write concern (adding an arbiter can do so) unless a cluster-wide default write
+//! concern was set with [`setDefaultRWConcern`].
+//! The action detects this case and fails with a clear error before reconfiguring.
+//!
+//! Config server replica sets do not support arbiters.
+//!
+//! The action remains running until the new configuration is committed.
+//!
+//! ## Arguments
+//!
+//! Arguments are required unless otherwise noted.
+//!
+//! The action has the following arguments:
+//!
+//! - `id` [OPTIONAL]: Index to use for the new arbiter `_id` attribute.
+//!   If not set, largest integer not currently in use is assigned.
+//! - `host`: Value of the new arbiter for the `host` attribute.
+//!
+//! [`replSetReconfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetReconfig/
+//! [`setDefaultRWConcern`]: https://www.mongodb.com/docs/manual/reference/command/setDefaultRWConcern/
+use std::future::IntoFuture;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Document;
+use mongodb::Client;
+use opentelemetry::trace::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+use crate::constants::ACTION_PREFIX;
+use crate::constants::CMD_GET_DEFAULT_RW_CONCERN;
+use crate::constants::DB_ADMIN;
+use crate::metrics::observe_mongodb_op;
+use crate::replicaset::actions::config;
+use crate::replicaset::actions::member;
+use crate::replicaset::actions::reconfig::Reconfiguration;
+
+/// First MongoDB major version that protects the implicit default write concern.
+const IMPLICIT_WRITE_CONCERN_SINCE: i32 = 5;
+
+/// Add an arbiter to the Replica Set cluster.
+#[derive(Debug)]
+pub struct AddArbiter;
+
+impl AddArbiter {
+    /// Registration metadata for the add arbiter action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/cluster.add-arbiter", ACTION_PREFIX);
+        ActionMetadata::build(kind, AddArbiter).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for AddArbiter {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args: AddArbiterArgs =
+            serde_json::from_value(action.args.clone()).context(AddArbiterError::InvalidArgs)?;
+        let client = crate::client::global();
+
+        // Get current RS configuration and resume in-progress reconfigurations.
+        let reconfig = Reconfiguration::load(&client, action)
+            .await
+            .context(AddArbiterError::Failed)?;
+        if reconfig.in_progress() {
+            return reconfig
+                .resume(context)
+                .await
+                .context(AddArbiterError::Failed);
+        }
+        let mut rs = reconfig.config().clone();
+        if rs.get_bool("configsvr").unwrap_or(false) {
+            anyhow::bail!(AddArbiterError::ConfigServer);
+        }
+
+        // Build new arbiter document.
+        let nid = match super::add::new_member_id(&rs, &args.host, args.id)? {
+            Some(nid) => nid,
+            None => {
+                slog::info!(
+                    context.logger, "Arbiter is already a member of the replica set";
+                    "host" => &args.host, "id" => ?args.id,
+                );
+                return Ok(Changes::to(ActionExecutionPhase::Done));
+            }
+        };
+        let node = mongodb::bson::doc! {
+            "_id": nid,
+            "host": args.host,
+            "arbiterOnly": true,
+        };
+
+        // Check the change is accepted by the server before reconfiguring.
+        let majority_before = implicit_majority_write_concern(&rs)?;
+        config::members_mut(&mut rs)?.push(node.clone().into());
+        member::validate_voters(&rs).context(AddArbiterError::InvalidArgs)?;
+        let majority_after = implicit_majority_write_concern(&rs)?;
+        if majority_before != majority_after {
+            let build_info = crate::client::admin::build_info(&client)
+                .await
+                .context(AddArbiterError::Failed)?;
+            let major_version = member::major_version(&build_info)?;
+            if major_version >= IMPLICIT_WRITE_CONCERN_SINCE
+                && !global_write_concern(&client).await?
+            {
+                anyhow::bail!(AddArbiterError::DefaultWriteConcernRequired);
+            }
+        }
+
+        // Reconfigure the replica set.
+        slog::info!(context.logger, "Adding arbiter to replica set"; "node" => %node);
+        let members = config::members(&rs)?.into_iter().cloned().collect();
+        reconfig
+            .start(context, members)
+            .await
+            .context(AddArbiterError::Failed)
+    }
+}
+
+/// Arguments to add a new arbiter to the replica set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AddArbiterArgs {
+    /// Index to use for the new arbiter `_id` attribute.
+    ///
+    /// If not set, largest integer not currently in use is assigned.
+    #[serde(default)]
+    pub id: Option<u32>,
+
+    /// Value of the new arbiter for the `host` attribute.
+    #[serde(alias = "node")]
+    pub host: String,
+}
+
+/// Errors encountered while adding the new arbiter.
+#[derive(Debug, thiserror::Error)]
+pub enum AddArbiterError {
+    /// Config server replica sets do not support arbiters.
+    #[error("config server replica sets do not support arbiters")]
+    ConfigServer,
+
+    /// Adding the arbiter changes the implicit default write concern.
+    #[error(
+        "adding the arbiter changes the implicit default write concern: \
+        set a cluster-wide default write concern with setDefaultRWConcern first"
+    )]
+    DefaultWriteConcernRequired,
+
+    /// Unable to add arbiter to replica set.
+    #[error("unable to add arbiter to replica set")]
+    Failed,
+
+    /// Arguments provided to the [`AddArbiter`] action are not valid.
+    #[error("arguments provided to the add arbiter action are not valid")]
+    InvalidArgs,
+}
+
+/// Check if a cluster-wide default write concern was explicitly set.
+async fn global_write_concern(client: &Client) -> Result<bool> {
+    let admin = client.database(DB_ADMIN);
+    let command = mongodb::bson::doc! {CMD_GET_DEFAULT_RW_CONCERN: 1};
+    let trace = crate::trace::mongodb_client_context(CMD_GET_DEFAULT_RW_CONCERN);
+    let (err_count, _timer) = observe_mongodb_op(CMD_GET_DEFAULT_RW_CONCERN);
+    let defaults = admin
+        .run_command(command)
+        .into_future()
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+        .context(AddArbiterError::Failed)?;
+    let global = match defaults.get_str("defaultWriteConcernSource") {
+        Ok(source) => source == "global",
+        Err(_) => defaults.contains_key("defaultWriteConcern"),
+    };
+    Ok(global)
+}
+
+/// Compute the implicit default write concern of a replica set configuration.
+///
+/// Returns `true` if the implicit default is `{w: "majority"}` and `false` for `{w: 1}`.
+/// The implicit default is `{w: 1}` only when arbiters are configured and data-bearing
+/// voting members are not more than a majority of voting members.
+fn implicit_majority_write_concern(rs: &Document) -> Result<bool> {
+    let mut arbiters = 0;
+    let mut voters = 0;
+    for member in config::members(rs)? {
+        if config::member_votes(member) == 0 {
+            continue;
+        }
+        voters += 1;
+        if member.get_bool("arbiterOnly").unwrap_or(false) {
+            arbiters += 1;
+        }
+    }
+    let majority = voters / 2 + 1;
+    let data_voters = voters - arbiters;
+    Ok(arbiters == 0 || data_voters > majority)
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+
+    use super::implicit_majority_write_concern;
+
+    #[test]
+    fn majority_without_arbiters() {
+        let rs = doc! {"members": [
+            {"_id": 0, "host": "mongo-0:27017"},
+            {"_id": 1, "host": "mongo-1:27017"},
+        ]};
+        assert!(implicit_majority_write_concern(&rs).unwrap());
+    }
+
+    #[test]
+    fn primary_secondary_arbiter() {
+        let rs = doc! {"members": [
+            {"_id": 0, "host": "mongo-0:27017"},
+            {"_id": 1, "host": "mongo-1:27017"},
+            {"_id": 2, "host": "mongo-2:27017", "arbiterOnly": true},
+        ]};
+        assert!(!implicit_majority_write_concern(&rs).unwrap());
+    }
+
+    #[test]
+    fn majority_with_many_data_voters() {
+        let rs = doc! {"members": [
+            {"_id": 0, "host": "mongo-0:27017"},
+            {"_id": 1, "host": "mongo-1:27017"},
+            {"_id": 2, "host": "mongo-2:27017"},
+            {"_id": 3, "host": "mongo-3:27017"},
+            {"_id": 4, "host": "mongo-4:27017", "arbiterOnly": true},
+        ]};
+        assert!(implicit_majority_write_concern(&rs).unwrap());
+    }
+}
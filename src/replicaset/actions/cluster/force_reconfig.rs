@@ -0,0 +1,218 @@
+//! Agent action to forcefully reconfigure the Replica Set after losing a majority of members.
+//!
+//! **DANGER**: this action is meant for disaster recovery only.
+//! Forced reconfigurations can roll back committed writes and, if the lost members
+//! are not actually lost, lead to diverging replica sets.
+//!
+//! The action builds a reduced configuration from the configuration known to the current node
+//! with only the surviving members and applies it with [`replSetReconfig`] and `force: true`.
+//! The action can run on any surviving member, including secondaries.
+//! The full configuration before and after the change is logged for audit purposes.
+//!
+//! If the configuration already includes only the surviving members
+//! the action completes without changes to the replica set.
+//!
+//! ## Arguments
+//!
+//! Arguments are required unless otherwise noted.
+//!
+//! The action has the following arguments:
+//!
+//! - `confirm`: Name of the replica set to reconfigure, as an explicit confirmation.
+//! - `hosts`: List of `host` attributes of the surviving members to keep.
+//!
+//! [`replSetReconfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetReconfig/
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+
+use crate::constants::ACTION_PREFIX;
+use crate::replicaset::actions::config;
+use crate::replicaset::actions::member;
+
+/// Forcefully reconfigure the Replica Set to keep only surviving members.
+#[derive(Debug)]
+pub struct ForceReconfig;
+
+impl ForceReconfig {
+    /// Registration metadata for the cluster force reconfiguration action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/cluster.force-reconfig", ACTION_PREFIX);
+        ActionMetadata::build(kind, ForceReconfig).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for ForceReconfig {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args: ForceReconfigArgs =
+            serde_json::from_value(action.args.clone()).context(ForceReconfigError::InvalidArgs)?;
+        let client = crate::client::global();
+
+        // Get the RS configuration known to the node and check the confirmation.
+        let rs = config::get(&client)
+            .await
+            .context(ForceReconfigError::Failed)?;
+        let name = rs
+            .get_str("_id")
+            .context(config::ConfigError::Attribute("_id"))?;
+        if args.confirm != name {
+            anyhow::bail!(ForceReconfigError::NotConfirmed {
+                expected: name.to_string(),
+            });
+        }
+
+        // Build the reduced configuration.
+        let reduced = match surviving_config(&rs, &args.hosts)? {
+            Some(reduced) => reduced,
+            None => {
+                slog::info!(
+                    context.logger, "Replica set already includes only the surviving members";
+                    "hosts" => ?args.hosts,
+                );
+                return Ok(Changes::to(ActionExecutionPhase::Done));
+            }
+        };
+
+        // Forcefully reconfigure the replica set.
+        slog::warn!(
+            context.logger, "Forcefully reconfiguring replica set";
+            "before" => %rs, "after" => %reduced,
+        );
+        config::reconfig_force(&client, reduced)
+            .await
+            .context(ForceReconfigError::Failed)?;
+        let changes = Changes::to(ActionExecutionPhase::Done);
+        Ok(changes)
+    }
+}
+
+/// Arguments to forcefully reconfigure the replica set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ForceReconfigArgs {
+    /// Name of the replica set to reconfigure, as an explicit confirmation.
+    pub confirm: String,
+
+    /// List of `host` attributes of the surviving members to keep.
+    pub hosts: Vec<String>,
+}
+
+/// Errors encountered while forcefully reconfiguring the replica set.
+#[derive(Debug, thiserror::Error)]
+pub enum ForceReconfigError {
+    /// Unable to forcefully reconfigure the replica set.
+    #[error("unable to forcefully reconfigure the replica set")]
+    Failed,
+
+    /// Arguments provided to the [`ForceReconfig`] action are not valid.
+    #[error("arguments provided to the force reconfig action are not valid")]
+    InvalidArgs,
+
+    /// No voting member would be left in the replica set.
+    #[error("no voting member would be left in the replica set")]
+    NoVoters,
+
+    /// The forced reconfiguration was not confirmed with the replica set name.
+    #[error("the forced reconfiguration must be confirmed with the replica set name '{expected}'")]
+    NotConfirmed { expected: String },
+
+    /// A surviving host is not a member of the replica set.
+    #[error("the surviving host '{0}' is not a member of the replica set")]
+    // (host,)
+    UnknownHost(String),
+}
+
+/// Build the replica set configuration with only the surviving members.
+///
+/// Returns `None` if the configuration already includes only the surviving members.
+fn surviving_config(rs: &Document, hosts: &[String]) -> Result<Option<Document>> {
+    let members = config::members(rs)?;
+    for host in hosts {
+        if config::member_index(rs, Some(host), None)?.is_none() {
+            anyhow::bail!(ForceReconfigError::UnknownHost(host.clone()));
+        }
+    }
+
+    let mut survivors = Vec::new();
+    for member in &members {
+        let host = config::member_host(member)?;
+        if hosts.iter().any(|survivor| survivor == host) {
+            survivors.push(Bson::Document((*member).clone()));
+        }
+    }
+    if survivors.len() == members.len() {
+        return Ok(None);
+    }
+
+    let mut reduced = rs.clone();
+    reduced.insert(config::RS_ATTR_MEMBERS, survivors);
+    config::bump_version(&mut reduced)?;
+    member::validate_voters(&reduced).context(ForceReconfigError::InvalidArgs)?;
+    let voters = config::members(&reduced)?
+        .into_iter()
+        .filter(|member| config::member_votes(member) > 0)
+        .count();
+    if voters == 0 {
+        anyhow::bail!(ForceReconfigError::NoVoters);
+    }
+    Ok(Some(reduced))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::surviving_config;
+    use super::ForceReconfigError;
+    use crate::replicaset::actions::fixtures::config;
+    use crate::replicaset::actions::fixtures::ids;
+    use crate::replicaset::actions::fixtures::member;
+    use crate::replicaset::actions::fixtures::non_voter;
+
+    #[test]
+    fn keep_survivors() {
+        let rs = config(4, vec![member(0), member(1), member(2), non_voter(3)]);
+        let hosts = vec!["mongo-2:27017".to_string(), "mongo-3:27017".to_string()];
+        let reduced = surviving_config(&rs, &hosts).unwrap().unwrap();
+        assert_eq!(reduced.get_i32("version").unwrap(), 5);
+        assert_eq!(ids(&reduced), vec![2, 3]);
+    }
+
+    #[test]
+    fn already_reduced() {
+        let rs = config(4, vec![member(0), member(1), member(2), non_voter(3)]);
+        let hosts: Vec<_> = (0..4).map(|id| format!("mongo-{}:27017", id)).collect();
+        let reduced = surviving_config(&rs, &hosts).unwrap();
+        assert_eq!(reduced, None);
+    }
+
+    #[test]
+    fn unknown_host() {
+        let rs = config(4, vec![member(0), member(1), member(2), non_voter(3)]);
+        let hosts = vec!["mongo-9:27017".to_string()];
+        let error = surviving_config(&rs, &hosts).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ForceReconfigError::UnknownHost(_))
+        ));
+    }
+
+    #[test]
+    fn no_voters_left() {
+        let rs = config(4, vec![member(0), member(1), member(2), non_voter(3)]);
+        let hosts = vec!["mongo-3:27017".to_string()];
+        let error = surviving_config(&rs, &hosts).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ForceReconfigError::NoVoters)
+        ));
+    }
+}
@@ -3,6 +3,9 @@
 //! The action will perform cluster initialisation using [`replSetInitiate`].
 //! If the replica set is already initialised this action returns an error.
 //!
+//! Standalone nodes can be converted into single-node replica sets with this action
+//! once they are restarted with the `--replSet` option.
+//!
 //! ## ReplicaSet configuration
 //!
 //! This action will configure a replica set based on the following options:
@@ -13,6 +16,7 @@
 //!   The host string for this node is defined in the `addresses.cluster` agent configuration.
 //! - The Replica Set `settings` can be specified to the action arguments.
 //!   The options are not checked and simply passed directly to the server.
+//! - When the agent runs in config server mode the replica set is marked with `configsvr: true`.
 //!
 //! [`getCmdLineOpts`]: https://www.mongodb.com/docs/manual/reference/command/getCmdLineOpts/
 //! [`replSetInitiate`]: https://www.mongodb.com/docs/manual/reference/command/replSetInitiate/
@@ -39,15 +43,22 @@ use crate::constants::CMD_GET_CMD_LINE_OPTS;
 use crate::constants::CMD_REPL_SET_INIT;
 use crate::constants::DB_ADMIN;
 use crate::metrics::observe_mongodb_op;
+use crate::replicaset::ReplicaSetMode;
 
 /// Initialise a MongoDB Replica Set cluster.
 #[derive(Debug)]
-pub struct Init;
+pub struct Init {
+    /// Initialise the replica set as a sharded cluster config server.
+    config_server: bool,
+}
 
 impl Init {
     /// Registration metadata for the cluster initialisation action.
-    pub fn metadata() -> ActionMetadata {
-        replisdk::agent::framework::actions::wellknown::cluster::init(Init)
+    pub fn metadata(mode: &ReplicaSetMode) -> ActionMetadata {
+        let init = Init {
+            config_server: matches!(mode, ReplicaSetMode::ConfigServer),
+        };
+        replisdk::agent::framework::actions::wellknown::cluster::init(init)
     }
 }
 
@@ -65,6 +76,9 @@ impl ActionHandler for Init {
         let status = crate::client::admin::replica_set_status(&client).await;
         match status {
             Err(error) if crate::client::admin::replica_set_not_initialised(&error) => (),
+            Err(error) if crate::client::admin::replication_not_enabled(&error) => {
+                anyhow::bail!(anyhow::anyhow!(error).context(InitError::ReplicationNotEnabled))
+            }
             Err(error) => anyhow::bail!(error),
             Ok(_) => anyhow::bail!(InitError::AlreadyInitialised),
         };
@@ -111,6 +125,9 @@ impl ActionHandler for Init {
                 "host": &self_host,
             }],
         };
+        if self.config_server {
+            init.insert("configsvr", true);
+        }
         if let Some(settings) = args.settings {
             init.insert("settings", settings);
         }
@@ -159,4 +176,8 @@ pub enum InitError {
     /// No replica set name was provided in MongoDB configuration or command.
     #[error("no replica set name was provided in MongoDB configuration or command")]
     NoReplicaSetName,
+
+    /// The node is not running with replication enabled (restart it with `--replSet`).
+    #[error("the node is not running with replication enabled (restart it with --replSet)")]
+    ReplicationNotEnabled,
 }
@@ -1,7 +1,19 @@
 //! Implementation of cluster management agent actions.
 
 mod add;
+mod add_arbiter;
+mod force_reconfig;
 mod init;
+mod remove;
+mod set_fcv;
+mod step_down;
+mod update_member;
 
 pub use self::add::Add;
+pub use self::add_arbiter::AddArbiter;
+pub use self::force_reconfig::ForceReconfig;
 pub use self::init::Init;
+pub use self::remove::Remove;
+pub use self::set_fcv::SetFcv;
+pub use self::step_down::StepDown;
+pub use self::update_member::UpdateMember;
@@ -0,0 +1,250 @@
+//! Agent action to remove a node from the current Replica Set.
+//!
+//! The action will reconfigure the replica set to remove a node with [`replSetReconfig`].
+//! If the current node is not the Replica Set primary the action will fail.
+//!
+//! To protect the Replica Set the action refuses to:
+//!
+//! - Remove the current primary (step it down first).
+//! - Remove a node if the remaining healthy voting members would not be a majority.
+//!
+//! If no member matches the arguments the node is considered removed already
+//! and the action completes without changes to the replica set.
+//! Otherwise the action remains running until the new configuration is committed.
+//!
+//! ## Arguments
+//!
+//! At least one of the arguments must be set.
+//! If both are set the member must match both.
+//!
+//! The action has the following arguments:
+//!
+//! - `host` [OPTIONAL]: Value of the `host` attribute of the member to remove.
+//! - `id` [OPTIONAL]: Value of the `_id` attribute of the member to remove.
+//!
+//! [`replSetReconfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetReconfig/
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Document;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+
+use crate::constants::MemberState;
+use crate::constants::ACTION_PREFIX;
+use crate::replicaset::actions::config;
+use crate::replicaset::actions::reconfig::Reconfiguration;
+
+/// Remove a node from the Replica Set cluster.
+#[derive(Debug)]
+pub struct Remove;
+
+impl Remove {
+    /// Registration metadata for the cluster remove action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/cluster.remove", ACTION_PREFIX);
+        ActionMetadata::build(kind, Remove).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for Remove {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args: RemoveArgs =
+            serde_json::from_value(action.args.clone()).context(RemoveError::InvalidArgs)?;
+        if args.host.is_none() && args.id.is_none() {
+            anyhow::bail!(RemoveError::NoMemberSelected);
+        }
+        let client = crate::client::global();
+
+        // Get current RS configuration and resume in-progress reconfigurations.
+        let reconfig = Reconfiguration::load(&client, action)
+            .await
+            .context(RemoveError::Failed)?;
+        if reconfig.in_progress() {
+            return reconfig.resume(context).await.context(RemoveError::Failed);
+        }
+        let mut rs = reconfig.config().clone();
+        let status = crate::client::admin::replica_set_status(&client)
+            .await
+            .context(RemoveError::Failed)?;
+
+        // Find the member to remove and check it is safe to do so.
+        let index = match member_to_remove(&rs, &status, &args)? {
+            Some(index) => index,
+            None => {
+                slog::info!(
+                    context.logger, "Node to remove is not in the replica set";
+                    "host" => ?args.host, "id" => ?args.id,
+                );
+                return Ok(Changes::to(ActionExecutionPhase::Done));
+            }
+        };
+
+        // Reconfigure the replica set.
+        let node = config::members_mut(&mut rs)?.remove(index);
+        slog::info!(context.logger, "Removing node from replica set"; "node" => %node);
+        let members = config::members(&rs)?.into_iter().cloned().collect();
+        reconfig
+            .start(context, members)
+            .await
+            .context(RemoveError::Failed)
+    }
+}
+
+/// Arguments to remove a node from the replica set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RemoveArgs {
+    /// Value of the `host` attribute of the member to remove.
+    #[serde(default, alias = "node")]
+    pub host: Option<String>,
+
+    /// Value of the `_id` attribute of the member to remove.
+    #[serde(default)]
+    pub id: Option<i32>,
+}
+
+/// Errors encountered while removing a node.
+#[derive(Debug, thiserror::Error)]
+pub enum RemoveError {
+    /// Unable to remove node from replica set.
+    #[error("unable to remove node from replica set")]
+    Failed,
+
+    /// Arguments provided to the [`Remove`] action are not valid.
+    #[error("arguments provided to the remove action are not valid")]
+    InvalidArgs,
+
+    /// Removing the node would leave the replica set without a healthy voting majority.
+    #[error("removing the node would leave the replica set without a healthy voting majority")]
+    MajorityLost,
+
+    /// Neither a host nor an ID was provided to select the member to remove.
+    #[error("neither a host nor an ID was provided to select the member to remove")]
+    NoMemberSelected,
+
+    /// Refusing to remove the current primary (step it down first).
+    #[error("refusing to remove the current primary (step it down first)")]
+    Primary,
+
+    /// Replica set status for a member is invalid.
+    #[error("replica set status for a member is invalid")]
+    StatusInvalid,
+}
+
+/// Find the index in the members list of the node to remove, if it is safe to remove it.
+///
+/// Returns `None` if no member matches the arguments.
+fn member_to_remove(rs: &Document, status: &Document, args: &RemoveArgs) -> Result<Option<usize>> {
+    let members = config::members(rs)?;
+    let index = match config::member_index(rs, args.host.as_deref(), args.id)? {
+        None => return Ok(None),
+        Some(index) => index,
+    };
+    let target_id = config::member_id(members[index])?;
+
+    // Collect the state of members from the replica set status.
+    let mut healthy = Vec::new();
+    for member in status
+        .get_array("members")
+        .context(RemoveError::StatusInvalid)?
+    {
+        let member = member.as_document().ok_or(RemoveError::StatusInvalid)?;
+        let id = member.get_i32("_id").context(RemoveError::StatusInvalid)?;
+        let state = member
+            .get_i32("state")
+            .unwrap_or(MemberState::Unknown as i32);
+        if id == target_id && state == MemberState::Primary as i32 {
+            anyhow::bail!(RemoveError::Primary);
+        }
+        if member.get_f64("health").unwrap_or(0.0) >= 1.0 {
+            healthy.push(id);
+        }
+    }
+
+    // Ensure the healthy voting members left are a majority of voting members left.
+    let mut voters = 0;
+    let mut healthy_voters = 0;
+    for member in members {
+        let id = config::member_id(member)?;
+        if id == target_id || config::member_votes(member) == 0 {
+            continue;
+        }
+        voters += 1;
+        if healthy.contains(&id) {
+            healthy_voters += 1;
+        }
+    }
+    if healthy_voters * 2 <= voters {
+        anyhow::bail!(RemoveError::MajorityLost);
+    }
+    Ok(Some(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::member_to_remove;
+    use super::RemoveArgs;
+    use super::RemoveError;
+    use crate::replicaset::actions::fixtures::config;
+    use crate::replicaset::actions::fixtures::member;
+    use crate::replicaset::actions::fixtures::non_voter;
+    use crate::replicaset::actions::fixtures::status;
+
+    #[test]
+    fn remove_by_host() {
+        let rs = config(3, vec![member(0), member(1), member(2), non_voter(3)]);
+        let status = status(&[(1, 1.0), (2, 1.0), (2, 1.0), (2, 1.0)]);
+        let args = RemoveArgs {
+            host: Some("mongo-2:27017".into()),
+            id: None,
+        };
+        let index = member_to_remove(&rs, &status, &args).unwrap();
+        assert_eq!(index, Some(2));
+    }
+
+    #[test]
+    fn remove_missing_member() {
+        let rs = config(3, vec![member(0), member(1), member(2), non_voter(3)]);
+        let status = status(&[(1, 1.0), (2, 1.0), (2, 1.0), (2, 1.0)]);
+        let args = RemoveArgs {
+            host: Some("mongo-2:27017".into()),
+            id: Some(1),
+        };
+        let index = member_to_remove(&rs, &status, &args).unwrap();
+        assert_eq!(index, None);
+    }
+
+    #[test]
+    fn refuse_primary() {
+        let rs = config(3, vec![member(0), member(1), member(2), non_voter(3)]);
+        let status = status(&[(1, 1.0), (2, 1.0), (2, 1.0), (2, 1.0)]);
+        let args = RemoveArgs {
+            host: None,
+            id: Some(0),
+        };
+        let error = member_to_remove(&rs, &status, &args).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(RemoveError::Primary)));
+    }
+
+    #[test]
+    fn refuse_majority_lost() {
+        let rs = config(3, vec![member(0), member(1), member(2), non_voter(3)]);
+        let status = status(&[(1, 1.0), (2, 1.0), (2, 0.0), (2, 1.0)]);
+        let args = RemoveArgs {
+            host: None,
+            id: Some(1),
+        };
+        let error = member_to_remove(&rs, &status, &args).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(RemoveError::MajorityLost)
+        ));
+    }
+}
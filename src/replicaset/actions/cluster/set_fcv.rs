@@ -0,0 +1,269 @@
+//! Agent action to change the Feature Compatibility Version (FCV) of the Replica Set.
+//!
+//! The action will set the FCV with [`setFeatureCompatibilityVersion`] to complete
+//! (or roll back) upgrades between major MongoDB versions.
+//! If the current node is not the Replica Set primary the action will fail.
+//!
+//! Downgrading the FCV is refused unless explicitly requested with the `allowDowngrade` argument.
+//! Starting with MongoDB 7.0 the server requires an explicit confirmation to change the FCV,
+//! which the action provides.
+//!
+//! The previous and new FCV are reported in the action state payload.
+//!
+//! ## Arguments
+//!
+//! Arguments are required unless otherwise noted.
+//!
+//! The action has the following arguments:
+//!
+//! - `version`: The FCV to set (for example `7.0`).
+//! - `allowDowngrade` [OPTIONAL]: Allow setting an FCV lower than the current one.
+//!
+//! [`setFeatureCompatibilityVersion`]: https://www.mongodb.com/docs/manual/reference/command/setFeatureCompatibilityVersion/
+use std::future::IntoFuture;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Document;
+use opentelemetry::trace::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+use crate::constants::MemberState;
+use crate::constants::ACTION_PREFIX;
+use crate::constants::CMD_SET_FEATURE_COMPATIBILITY_VERSION;
+use crate::constants::DB_ADMIN;
+use crate::metrics::observe_mongodb_op;
+use crate::replicaset::actions::member;
+
+/// First MongoDB major version requiring confirmation to change the FCV.
+const CONFIRM_FCV_SINCE: i32 = 7;
+
+/// Change the Feature Compatibility Version of the Replica Set.
+#[derive(Debug)]
+pub struct SetFcv;
+
+impl SetFcv {
+    /// Registration metadata for the set feature compatibility version action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/cluster.set-fcv", ACTION_PREFIX);
+        ActionMetadata::build(kind, SetFcv).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for SetFcv {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args: SetFcvArgs =
+            serde_json::from_value(action.args.clone()).context(SetFcvError::InvalidArgs)?;
+        parse_version(&args.version)?;
+        let client = crate::client::global();
+
+        // The FCV can only be changed on the primary.
+        let status = crate::client::admin::replica_set_status(&client)
+            .await
+            .context(SetFcvError::Failed)?;
+        if status.get_i32("myState") != Ok(MemberState::Primary as i32) {
+            anyhow::bail!(SetFcvError::NotPrimary);
+        }
+
+        // Check the FCV change requested.
+        let previous = crate::replicaset::info::feature_compatibility_version(&client)
+            .await
+            .context(SetFcvError::Failed)?;
+        let payload = serde_json::json!({
+            "previous": &previous,
+            "new": &args.version,
+        });
+        if !fcv_change_needed(&previous, &args)? {
+            slog::info!(
+                context.logger, "Feature compatibility version already set";
+                "version" => &args.version,
+            );
+            return Ok(Changes::to(ActionExecutionPhase::Done).payload(payload));
+        }
+
+        // Set the new FCV.
+        let build_info = crate::client::admin::build_info(&client)
+            .await
+            .context(SetFcvError::Failed)?;
+        let major_version = member::major_version(&build_info)?;
+        let command = fcv_command(&args.version, major_version);
+
+        slog::info!(
+            context.logger, "Setting feature compatibility version";
+            "previous" => &previous, "new" => &args.version,
+        );
+        let admin = client.database(DB_ADMIN);
+        let trace = crate::trace::mongodb_client_context(CMD_SET_FEATURE_COMPATIBILITY_VERSION);
+        let (err_count, _timer) = observe_mongodb_op(CMD_SET_FEATURE_COMPATIBILITY_VERSION);
+        admin
+            .run_command(command)
+            .into_future()
+            .count_on_err(err_count)
+            .trace_on_err_with_status()
+            .with_context(trace)
+            .await
+            .context(SetFcvError::Failed)?;
+        let changes = Changes::to(ActionExecutionPhase::Done).payload(payload);
+        Ok(changes)
+    }
+}
+
+/// Arguments to set the feature compatibility version.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SetFcvArgs {
+    /// Allow setting an FCV lower than the current one.
+    #[serde(default, alias = "allowDowngrade")]
+    pub allow_downgrade: bool,
+
+    /// The FCV to set.
+    pub version: String,
+}
+
+/// Errors encountered while setting the feature compatibility version.
+#[derive(Debug, thiserror::Error)]
+pub enum SetFcvError {
+    /// Refusing to downgrade the FCV without an explicit request.
+    #[error(
+        "refusing to downgrade the FCV from {current} to {target} (set allowDowngrade to do so)"
+    )]
+    DowngradeRefused { current: String, target: String },
+
+    /// Unable to set the feature compatibility version.
+    #[error("unable to set the feature compatibility version")]
+    Failed,
+
+    /// Arguments provided to the [`SetFcv`] action are not valid.
+    #[error("arguments provided to the set FCV action are not valid")]
+    InvalidArgs,
+
+    /// The feature compatibility version can only be changed on the primary.
+    #[error("the feature compatibility version can only be changed on the primary")]
+    NotPrimary,
+
+    /// The feature compatibility version is not in the `major.minor` format.
+    #[error("the feature compatibility version '{0}' is not in the major.minor format")]
+    // (version,)
+    VersionInvalid(String),
+}
+
+/// Check if the FCV needs to change from the `previous` version to the requested one.
+///
+/// Downgrades are refused unless explicitly allowed.
+fn fcv_change_needed(previous: &str, args: &SetFcvArgs) -> Result<bool> {
+    let current = parse_version(previous)?;
+    let target = parse_version(&args.version)?;
+    if current == target {
+        return Ok(false);
+    }
+    if target < current && !args.allow_downgrade {
+        anyhow::bail!(SetFcvError::DowngradeRefused {
+            current: previous.to_string(),
+            target: args.version.clone(),
+        });
+    }
+    Ok(true)
+}
+
+/// Build the command to set the FCV on a server with the given major version.
+fn fcv_command(version: &str, major_version: i32) -> Document {
+    let mut command = mongodb::bson::doc! {
+        CMD_SET_FEATURE_COMPATIBILITY_VERSION: version,
+    };
+    if major_version >= CONFIRM_FCV_SINCE {
+        command.insert("confirm", true);
+    }
+    command
+}
+
+/// Parse a `major.minor` feature compatibility version for comparison.
+fn parse_version(version: &str) -> Result<(u32, u32)> {
+    let invalid = || SetFcvError::VersionInvalid(version.to_string());
+    let (major, minor) = version.split_once('.').ok_or_else(invalid)?;
+    let major = major.parse().map_err(|_| invalid())?;
+    let minor = minor.parse().map_err(|_| invalid())?;
+    Ok((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+
+    use super::fcv_change_needed;
+    use super::fcv_command;
+    use super::parse_version;
+    use super::SetFcvArgs;
+    use super::SetFcvError;
+
+    fn args(version: &str, allow_downgrade: bool) -> SetFcvArgs {
+        SetFcvArgs {
+            allow_downgrade,
+            version: version.into(),
+        }
+    }
+
+    #[test]
+    fn upgrade_needed() {
+        assert!(fcv_change_needed("6.0", &args("7.0", false)).unwrap());
+    }
+
+    #[test]
+    fn already_set() {
+        assert!(!fcv_change_needed("7.0", &args("7.0", false)).unwrap());
+        assert!(!fcv_change_needed("7.0", &args("7.0", true)).unwrap());
+    }
+
+    #[test]
+    fn downgrade_refused() {
+        let error = fcv_change_needed("7.0", &args("6.0", false)).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(SetFcvError::DowngradeRefused { .. })
+        ));
+    }
+
+    #[test]
+    fn downgrade_allowed() {
+        assert!(fcv_change_needed("7.0", &args("6.0", true)).unwrap());
+    }
+
+    #[test]
+    fn confirm_since_7() {
+        let command = fcv_command("6.0", 6);
+        assert_eq!(command, doc! {"setFeatureCompatibilityVersion": "6.0"});
+        let command = fcv_command("7.0", 7);
+        assert_eq!(
+            command,
+            doc! {"setFeatureCompatibilityVersion": "7.0", "confirm": true}
+        );
+        let command = fcv_command("8.0", 8);
+        assert_eq!(command.get_bool("confirm"), Ok(true));
+    }
+
+    #[test]
+    fn versions_compare() {
+        let old = parse_version("4.4").unwrap();
+        let new = parse_version("5.0").unwrap();
+        assert!(old < new);
+        assert!(parse_version("6.0").unwrap() < parse_version("6.10").unwrap());
+    }
+
+    #[test]
+    fn version_invalid() {
+        let error = parse_version("7").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(SetFcvError::VersionInvalid(_))
+        ));
+    }
+}
@@ -0,0 +1,251 @@
+//! Agent action to step down the current Replica Set primary.
+//!
+//! The action will ask the primary to step down with [`replSetStepDown`], making the node
+//! ineligible to become primary again for the requested time.
+//! If the current node is not the Replica Set primary the action completes without changes.
+//!
+//! Before MongoDB 4.2 the server closes all client connections when it steps down.
+//! The resulting connection error is expected and treated as a successful step down.
+//!
+//! Stepped down nodes are frozen for `stepDownSecs` and report it like the `node.freeze` action.
+//!
+//! The action completes once [`replSetGetStatus`] reports the node is no longer primary.
+//! If the node is still (or again) primary when the action is next invoked the step down
+//! is requested again, up to a limited number of attempts before the action fails.
+//!
+//! ## Arguments
+//!
+//! Arguments are optional and default to the server defaults.
+//!
+//! The action has the following arguments:
+//!
+//! - `stepDownSecs` [OPTIONAL]: Seconds the node is ineligible to become primary (default 60).
+//! - `secondaryCatchUpPeriodSecs` [OPTIONAL]: Seconds to wait for an electable secondary
+//!   to catch up with the primary before stepping down (default 10).
+//!
+//! [`replSetGetStatus`]: https://www.mongodb.com/docs/manual/reference/command/replSetGetStatus/
+//! [`replSetStepDown`]: https://www.mongodb.com/docs/manual/reference/command/replSetStepDown/
+use std::future::IntoFuture;
+use std::time::Duration;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Document;
+use mongodb::Client;
+use opentelemetry::trace::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+use crate::constants::MemberState;
+use crate::constants::ACTION_PREFIX;
+use crate::constants::CMD_REPL_SET_STEP_DOWN;
+use crate::constants::DB_ADMIN;
+use crate::metrics::observe_mongodb_op;
+
+/// Default number of seconds the node is ineligible to become primary.
+const DEFAULT_STEP_DOWN_SECS: u32 = 60;
+
+/// Default number of seconds to wait for an electable secondary to catch up.
+const DEFAULT_SECONDARY_CATCH_UP_PERIOD_SECS: u32 = 10;
+
+/// Maximum number of times the node is asked to step down before the action fails.
+const MAX_STEP_DOWN_ATTEMPTS: u64 = 3;
+
+/// Name of the action payload attribute recording the number of step down requests.
+const PAYLOAD_ATTEMPTS: &str = "attempts";
+
+/// Step down the Replica Set primary.
+#[derive(Debug)]
+pub struct StepDown;
+
+impl StepDown {
+    /// Registration metadata for the cluster step down action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/cluster.stepdown", ACTION_PREFIX);
+        ActionMetadata::build(kind, StepDown).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for StepDown {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args = serde_json::from_value::<Option<StepDownArgs>>(action.args.clone())
+            .context(StepDownError::InvalidArgs)?
+            .unwrap_or_default();
+        if args.step_down_secs <= args.secondary_catch_up_period_secs {
+            let error = anyhow::anyhow!("stepDownSecs must be greater than the catch up period");
+            anyhow::bail!(error.context(StepDownError::InvalidArgs));
+        }
+        let client = crate::client::global();
+        let attempts = action
+            .state
+            .payload
+            .as_ref()
+            .and_then(|payload| payload.get(PAYLOAD_ATTEMPTS))
+            .and_then(|attempts| attempts.as_u64())
+            .unwrap_or(0);
+
+        // Check if the node is still primary.
+        if !is_primary(&client).await? {
+            return Ok(Changes::to(ActionExecutionPhase::Done));
+        }
+        if attempts >= MAX_STEP_DOWN_ATTEMPTS {
+            anyhow::bail!(StepDownError::StillPrimary);
+        }
+
+        // Request the primary to step down.
+        let attempts = attempts + 1;
+        slog::info!(
+            context.logger, "Stepping down replica set primary";
+            "attempt" => attempts,
+            "step_down_secs" => args.step_down_secs,
+            "secondary_catch_up_period_secs" => args.secondary_catch_up_period_secs,
+        );
+        step_down(&client, &args).await?;
+        let frozen = Duration::from_secs(args.step_down_secs.into());
+        crate::replicaset::state::freeze(frozen);
+
+        // Check the node actually stepped down or wait for it to do so.
+        if !is_primary(&client).await? {
+            return Ok(Changes::to(ActionExecutionPhase::Done));
+        }
+        let payload = serde_json::json!({ PAYLOAD_ATTEMPTS: attempts });
+        Ok(Changes::to(ActionExecutionPhase::Running).payload(payload))
+    }
+}
+
+/// Arguments to step down the replica set primary.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepDownArgs {
+    /// Seconds the node is ineligible to become primary.
+    #[serde(
+        default = "StepDownArgs::default_step_down_secs",
+        alias = "stepDownSecs"
+    )]
+    pub step_down_secs: u32,
+
+    /// Seconds to wait for an electable secondary to catch up with the primary.
+    #[serde(
+        default = "StepDownArgs::default_secondary_catch_up_period_secs",
+        alias = "secondaryCatchUpPeriodSecs"
+    )]
+    pub secondary_catch_up_period_secs: u32,
+}
+
+impl Default for StepDownArgs {
+    fn default() -> Self {
+        StepDownArgs {
+            step_down_secs: DEFAULT_STEP_DOWN_SECS,
+            secondary_catch_up_period_secs: DEFAULT_SECONDARY_CATCH_UP_PERIOD_SECS,
+        }
+    }
+}
+
+impl StepDownArgs {
+    fn default_step_down_secs() -> u32 {
+        DEFAULT_STEP_DOWN_SECS
+    }
+
+    fn default_secondary_catch_up_period_secs() -> u32 {
+        DEFAULT_SECONDARY_CATCH_UP_PERIOD_SECS
+    }
+}
+
+/// Errors encountered while stepping down the primary.
+#[derive(Debug, thiserror::Error)]
+pub enum StepDownError {
+    /// Unable to step down the replica set primary.
+    #[error("unable to step down the replica set primary")]
+    Failed,
+
+    /// Arguments provided to the [`StepDown`] action are not valid.
+    #[error("arguments provided to the step down action are not valid")]
+    InvalidArgs,
+
+    /// Replica set status does not report the state of the node.
+    #[error("replica set status does not report the state of the node")]
+    StatusInvalid,
+
+    /// The node is still primary after repeatedly stepping down.
+    #[error("the node is still primary after repeatedly stepping down")]
+    StillPrimary,
+}
+
+/// Check if the node is the replica set primary.
+async fn is_primary(client: &Client) -> Result<bool> {
+    let status = crate::client::admin::replica_set_status(client)
+        .await
+        .context(StepDownError::Failed)?;
+    status_is_primary(&status)
+}
+
+/// Check if a replica set status reports the node is primary.
+fn status_is_primary(status: &Document) -> Result<bool> {
+    let state = status
+        .get_i32("myState")
+        .context(StepDownError::StatusInvalid)?;
+    Ok(state == MemberState::Primary as i32)
+}
+
+/// Run the [`replSetStepDown`] command, ignoring expected connection errors.
+///
+/// [`replSetStepDown`]: https://www.mongodb.com/docs/manual/reference/command/replSetStepDown/
+async fn step_down(client: &Client, args: &StepDownArgs) -> Result<()> {
+    let admin = client.database(DB_ADMIN);
+    let command = mongodb::bson::doc! {
+        CMD_REPL_SET_STEP_DOWN: args.step_down_secs,
+        "secondaryCatchUpPeriodSecs": args.secondary_catch_up_period_secs,
+    };
+    let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_STEP_DOWN);
+    let (err_count, _timer) = observe_mongodb_op(CMD_REPL_SET_STEP_DOWN);
+
+    // Connection errors are expected so only unexpected errors are counted.
+    let result = admin
+        .run_command(command)
+        .into_future()
+        .trace_on_err()
+        .with_context(trace)
+        .await;
+    match result {
+        Err(error) if crate::client::admin::connection_closed(&error) => Ok(()),
+        Err(error) => {
+            err_count.inc();
+            anyhow::bail!(anyhow::anyhow!(error).context(StepDownError::Failed))
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+
+    use super::status_is_primary;
+    use super::StepDownArgs;
+
+    #[test]
+    fn args_defaults() {
+        let args: Option<StepDownArgs> = serde_json::from_value(serde_json::json!({
+            "stepDownSecs": 120,
+        }))
+        .unwrap();
+        let args = args.unwrap();
+        assert_eq!(args.step_down_secs, 120);
+        assert_eq!(args.secondary_catch_up_period_secs, 10);
+    }
+
+    #[test]
+    fn primary_state() {
+        assert!(status_is_primary(&doc! {"myState": 1}).unwrap());
+        assert!(!status_is_primary(&doc! {"myState": 2}).unwrap());
+        assert!(status_is_primary(&doc! {}).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+//! Agent action to change the configuration of an existing Replica Set member.
+//!
+//! The action will reconfigure the replica set with [`replSetReconfig`] to update
+//! the attributes of the selected member.
+//! If the current node is not the Replica Set primary the action will fail.
+//!
+//! Updated members are validated before the replica set is reconfigured:
+//!
+//! - Members with priority greater than 0 must not be hidden.
+//! - The replica set can have at most 7 voting members.
+//!
+//! The replica set is reconfigured in safe steps and the action remains running
+//! until the new configuration is committed.
+//!
+//! ## Arguments
+//!
+//! At least one of `host` or `id` must be set.
+//! If both are set the member must match both.
+//!
+//! The action has the following arguments:
+//!
+//! - `host` [OPTIONAL]: Value of the `host` attribute of the member to update.
+//! - `id` [OPTIONAL]: Value of the `_id` attribute of the member to update.
+//! - `hidden` [OPTIONAL]: Hide the member from clients (requires priority 0).
+//! - `priority` [OPTIONAL]: Election priority of the member (0 to 1000).
+//! - `secondaryDelaySecs` [OPTIONAL]: Seconds the member lags behind the primary.
+//!   Also accepted as `slaveDelay` and sent to the server with the name it supports.
+//! - `tags` [OPTIONAL]: Map of tags to set on the member (replaces existing tags).
+//! - `votes` [OPTIONAL]: Number of votes the member has (0 or 1).
+//!
+//! Attributes that are not set are left unchanged.
+//! If the member already has the requested attributes the action completes without changes.
+//! The `buildIndexes` attribute can't be changed on existing members.
+//!
+//! [`replSetReconfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetReconfig/
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+
+use crate::constants::ACTION_PREFIX;
+use crate::replicaset::actions::config;
+use crate::replicaset::actions::member;
+use crate::replicaset::actions::member::MemberOptions;
+use crate::replicaset::actions::reconfig;
+use crate::replicaset::actions::reconfig::Reconfiguration;
+
+/// Change the configuration of an existing Replica Set member.
+#[derive(Debug)]
+pub struct UpdateMember;
+
+impl UpdateMember {
+    /// Registration metadata for the cluster update member action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/cluster.update-member", ACTION_PREFIX);
+        ActionMetadata::build(kind, UpdateMember).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for UpdateMember {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args: UpdateMemberArgs =
+            serde_json::from_value(action.args.clone()).context(UpdateMemberError::InvalidArgs)?;
+        check_args(&args)?;
+        let client = crate::client::global();
+
+        // Get current RS configuration and resume in-progress reconfigurations.
+        let reconfig = Reconfiguration::load(&client, action)
+            .await
+            .context(UpdateMemberError::Failed)?;
+        if reconfig.in_progress() {
+            return reconfig
+                .resume(context)
+                .await
+                .context(UpdateMemberError::Failed);
+        }
+
+        // Find and update the member.
+        let build_info = crate::client::admin::build_info(&client)
+            .await
+            .context(UpdateMemberError::Failed)?;
+        let major_version = member::major_version(&build_info)?;
+        let rs = match updated_config(reconfig.config(), &args, major_version)? {
+            None => {
+                slog::info!(context.logger, "Replica set member already up to date");
+                return Ok(Changes::to(ActionExecutionPhase::Done));
+            }
+            Some(rs) => rs,
+        };
+
+        // Reconfigure the replica set.
+        slog::info!(
+            context.logger, "Updating replica set member";
+            "host" => ?args.host, "id" => ?args.id,
+        );
+        let members = config::members(&rs)?.into_iter().cloned().collect();
+        reconfig
+            .start(context, members)
+            .await
+            .context(UpdateMemberError::Failed)
+    }
+}
+
+/// Check the action arguments before the replica set configuration is looked up.
+fn check_args(args: &UpdateMemberArgs) -> Result<()> {
+    if args.host.is_none() && args.id.is_none() {
+        anyhow::bail!(UpdateMemberError::NoMemberSelected);
+    }
+    if args.options.build_indexes.is_some() {
+        let error = anyhow::anyhow!("buildIndexes can't be changed on existing members");
+        anyhow::bail!(error.context(UpdateMemberError::InvalidArgs));
+    }
+    Ok(())
+}
+
+/// Replica set configuration with the selected member updated.
+///
+/// Returns `None` if the member already has the requested attributes.
+fn updated_config(
+    rs: &Document,
+    args: &UpdateMemberArgs,
+    major_version: i32,
+) -> Result<Option<Document>> {
+    let index = config::member_index(rs, args.host.as_deref(), args.id)?.ok_or_else(|| {
+        UpdateMemberError::MemberNotFound {
+            host: args.host.clone(),
+            id: args.id,
+        }
+    })?;
+    let current = config::members(rs)?[index];
+    let mut node = current.clone();
+    args.options.apply(&mut node, major_version);
+    if reconfig::same_value(
+        &Bson::Document(current.clone()),
+        &Bson::Document(node.clone()),
+    ) {
+        return Ok(None);
+    }
+    member::validate(&node).context(UpdateMemberError::InvalidArgs)?;
+
+    let mut rs = rs.clone();
+    config::members_mut(&mut rs)?[index] = Bson::Document(node);
+    member::validate_voters(&rs).context(UpdateMemberError::InvalidArgs)?;
+    Ok(Some(rs))
+}
+
+/// Arguments to update an existing replica set member.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UpdateMemberArgs {
+    /// Value of the `host` attribute of the member to update.
+    #[serde(default, alias = "node")]
+    pub host: Option<String>,
+
+    /// Value of the `_id` attribute of the member to update.
+    #[serde(default)]
+    pub id: Option<i32>,
+
+    /// Attributes of the member to change.
+    #[serde(flatten)]
+    pub options: MemberOptions,
+}
+
+/// Errors encountered while updating a member.
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateMemberError {
+    /// Unable to update the replica set member.
+    #[error("unable to update the replica set member")]
+    Failed,
+
+    /// Arguments provided to the [`UpdateMember`] action are not valid.
+    #[error("arguments provided to the update member action are not valid")]
+    InvalidArgs,
+
+    /// No replica set member matches the selectors.
+    #[error("no replica set member matches host {host:?} and _id {id:?}")]
+    MemberNotFound {
+        host: Option<String>,
+        id: Option<i32>,
+    },
+
+    /// Neither a host nor an ID was provided to select the member to update.
+    #[error("neither a host nor an ID was provided to select the member to update")]
+    NoMemberSelected,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_args;
+    use super::updated_config;
+    use super::UpdateMemberArgs;
+    use super::UpdateMemberError;
+    use crate::replicaset::actions::config;
+    use crate::replicaset::actions::fixtures::config as rs_config;
+    use crate::replicaset::actions::fixtures::non_voter;
+    use crate::replicaset::actions::fixtures::voter;
+    use crate::replicaset::actions::member::MemberError;
+
+    fn args(args: serde_json::Value) -> UpdateMemberArgs {
+        serde_json::from_value(args).unwrap()
+    }
+
+    fn priority(rs: &mongodb::bson::Document, index: usize) -> f64 {
+        config::members(rs).unwrap()[index]
+            .get_f64("priority")
+            .unwrap()
+    }
+
+    #[test]
+    fn select_by_host() {
+        let rs = rs_config(1, (0..3).map(voter).collect());
+        let args = args(serde_json::json!({"host": "mongo-1:27017", "priority": 2}));
+        let rs = updated_config(&rs, &args, 6).unwrap().unwrap();
+        assert_eq!(priority(&rs, 1), 2.0);
+        assert_eq!(priority(&rs, 0), 1.0);
+    }
+
+    #[test]
+    fn select_by_id() {
+        let rs = rs_config(1, (0..3).map(voter).collect());
+        let args = args(serde_json::json!({"id": 2, "priority": 2}));
+        let rs = updated_config(&rs, &args, 6).unwrap().unwrap();
+        assert_eq!(priority(&rs, 2), 2.0);
+    }
+
+    #[test]
+    fn select_by_host_and_id() {
+        let rs = rs_config(1, (0..3).map(voter).collect());
+        let args = args(serde_json::json!({"host": "mongo-2:27017", "id": 2, "priority": 2}));
+        let rs = updated_config(&rs, &args, 6).unwrap().unwrap();
+        assert_eq!(priority(&rs, 2), 2.0);
+    }
+
+    #[test]
+    fn select_mismatched_host_and_id() {
+        let rs = rs_config(1, (0..3).map(voter).collect());
+        let args = args(serde_json::json!({"host": "mongo-2:27017", "id": 1, "priority": 2}));
+        let error = updated_config(&rs, &args, 6).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UpdateMemberError::MemberNotFound { id: Some(1), .. })
+        ));
+    }
+
+    #[test]
+    fn refuse_build_indexes() {
+        let args = args(serde_json::json!({"id": 1, "buildIndexes": false}));
+        let error = check_args(&args).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UpdateMemberError::InvalidArgs)
+        ));
+    }
+
+    #[test]
+    fn refuse_hidden_with_priority() {
+        let rs = rs_config(1, (0..3).map(voter).collect());
+        let args = args(serde_json::json!({"id": 1, "hidden": true}));
+        let error = updated_config(&rs, &args, 6).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UpdateMemberError::InvalidArgs)
+        ));
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(MemberError::PriorityWithHidden)
+        ));
+    }
+
+    #[test]
+    fn refuse_too_many_voters() {
+        let mut members: Vec<_> = (0..7).map(voter).collect();
+        members.push(non_voter(7));
+        let rs = rs_config(1, members);
+        let args = args(serde_json::json!({"id": 7, "votes": 1}));
+        let error = updated_config(&rs, &args, 6).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UpdateMemberError::InvalidArgs)
+        ));
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(MemberError::TooManyVoters(8))
+        ));
+    }
+
+    #[test]
+    fn unchanged_member() {
+        let rs = rs_config(1, (0..3).map(voter).collect());
+        let args = args(serde_json::json!({"id": 1, "priority": 1, "votes": 1}));
+        let rs = updated_config(&rs, &args, 6).unwrap();
+        assert_eq!(rs, None);
+    }
+}
@@ -0,0 +1,239 @@
+//! Utilities to fetch, inspect and update the Replica Set configuration.
+use std::future::IntoFuture;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use mongodb::Client;
+use opentelemetry::trace::FutureExt;
+
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+use crate::constants::CMD_REPL_SET_GET_CONFIG;
+use crate::constants::CMD_REPL_SET_RECONFIG;
+use crate::constants::DB_ADMIN;
+use crate::metrics::observe_mongodb_op;
+
+pub const RS_ATTR_MEMBER_HOST: &str = "host";
+pub const RS_ATTR_MEMBER_ID: &str = "_id";
+pub const RS_ATTR_MEMBER_VOTES: &str = "votes";
+pub const RS_ATTR_MEMBERS: &str = "members";
+pub const RS_ATTR_VERSION: &str = "version";
+
+/// Fetch the Replica Set configuration known to the node with [`replSetGetConfig`].
+///
+/// Unlike [`get_with_commitment`] this function works on any member, not just the primary.
+///
+/// [`replSetGetConfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetGetConfig/
+pub async fn get(client: &Client) -> Result<Document> {
+    let command = mongodb::bson::doc! {CMD_REPL_SET_GET_CONFIG: 1};
+    let (rs, _) = get_with_command(client, command).await?;
+    Ok(rs)
+}
+
+/// Fetch the current Replica Set configuration and its commitment status with [`replSetGetConfig`].
+///
+/// The commitment status is `true` when the configuration has propagated to
+/// a majority of voting members and a new reconfiguration can be applied safely.
+/// Servers that do not report a commitment status (before MongoDB 4.4) are assumed committed.
+///
+/// [`replSetGetConfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetGetConfig/
+pub async fn get_with_commitment(client: &Client) -> Result<(Document, bool)> {
+    let command = mongodb::bson::doc! {
+        CMD_REPL_SET_GET_CONFIG: 1,
+        "commitmentStatus": true,
+    };
+    get_with_command(client, command).await
+}
+
+/// Run a `replSetGetConfig` command and decode the configuration and commitment status.
+async fn get_with_command(client: &Client, command: Document) -> Result<(Document, bool)> {
+    let admin = client.database(DB_ADMIN);
+    let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_GET_CONFIG);
+    let (err_count, timer) = observe_mongodb_op(CMD_REPL_SET_GET_CONFIG);
+    let mut response = admin
+        .run_command(command)
+        .into_future()
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+        .context(ConfigError::GetFailed)?;
+    drop(timer);
+    let committed = response.get_bool("commitmentStatus").ok();
+    let rs = response
+        .remove("config")
+        .ok_or_else(|| anyhow::anyhow!("server did not return replica set configuration"))
+        .context(ConfigError::Invalid)?;
+    match rs {
+        Bson::Document(rs) => Ok((rs, committed.unwrap_or(true))),
+        _ => {
+            let error = anyhow::anyhow!("server returned invalid type for rs configuration");
+            anyhow::bail!(error.context(ConfigError::Invalid))
+        }
+    }
+}
+
+/// Apply a new Replica Set configuration with [`replSetReconfig`].
+///
+/// The configuration `version` is NOT incremented by this function.
+///
+/// [`replSetReconfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetReconfig/
+pub async fn reconfig(client: &Client, rs: Document) -> Result<()> {
+    let command = mongodb::bson::doc! {CMD_REPL_SET_RECONFIG: rs};
+    reconfig_with_command(client, command).await
+}
+
+/// Forcefully apply a new Replica Set configuration with [`replSetReconfig`].
+///
+/// Forced reconfigurations can be applied on secondaries and do not require a majority
+/// of voting members to be available but may cause committed writes to be rolled back.
+/// They should only be used to recover from the permanent loss of a majority of members.
+///
+/// [`replSetReconfig`]: https://www.mongodb.com/docs/manual/reference/command/replSetReconfig/
+pub async fn reconfig_force(client: &Client, rs: Document) -> Result<()> {
+    let command = mongodb::bson::doc! {
+        CMD_REPL_SET_RECONFIG: rs,
+        "force": true,
+    };
+    reconfig_with_command(client, command).await
+}
+
+/// Run a `replSetReconfig` command.
+async fn reconfig_with_command(client: &Client, command: Document) -> Result<()> {
+    let admin = client.database(DB_ADMIN);
+    let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_RECONFIG);
+    let (err_count, _timer) = observe_mongodb_op(CMD_REPL_SET_RECONFIG);
+    admin
+        .run_command(command)
+        .into_future()
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+        .context(ConfigError::ReconfigFailed)?;
+    Ok(())
+}
+
+/// Increment the Replica Set configuration `version` ahead of a reconfiguration.
+pub fn bump_version(rs: &mut Document) -> Result<()> {
+    let version = rs
+        .get_i32_mut(RS_ATTR_VERSION)
+        .context(ConfigError::Attribute(RS_ATTR_VERSION))?;
+    *version += 1;
+    Ok(())
+}
+
+/// Access the list of members in the Replica Set configuration.
+pub fn members(rs: &Document) -> Result<Vec<&Document>> {
+    rs.get_array(RS_ATTR_MEMBERS)
+        .context(ConfigError::Attribute(RS_ATTR_MEMBERS))?
+        .iter()
+        .map(|member| {
+            member
+                .as_document()
+                .ok_or_else(|| anyhow::anyhow!("elements in members array must be an object"))
+                .context(ConfigError::Invalid)
+        })
+        .collect()
+}
+
+/// Mutably access the list of members in the Replica Set configuration.
+pub fn members_mut(rs: &mut Document) -> Result<&mut Vec<Bson>> {
+    rs.get_array_mut(RS_ATTR_MEMBERS)
+        .context(ConfigError::Attribute(RS_ATTR_MEMBERS))
+}
+
+/// Find the index in the members list of the member matching the given `host` and `_id`.
+///
+/// Selectors that are not set match all members.
+/// Returns `None` if no member matches the selectors.
+pub fn member_index(rs: &Document, host: Option<&str>, id: Option<i32>) -> Result<Option<usize>> {
+    for (index, member) in members(rs)?.into_iter().enumerate() {
+        let member_host = member_host(member)?;
+        let member_id = member_id(member)?;
+        let host_matches = host.map(|h| h == member_host).unwrap_or(true);
+        let id_matches = id.map(|i| i == member_id).unwrap_or(true);
+        if host_matches && id_matches {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+/// Lookup the `_id` of a Replica Set member configuration.
+pub fn member_id(member: &Document) -> Result<i32> {
+    member
+        .get_i32(RS_ATTR_MEMBER_ID)
+        .context(ConfigError::Attribute(RS_ATTR_MEMBER_ID))
+}
+
+/// Lookup the `host` of a Replica Set member configuration.
+pub fn member_host(member: &Document) -> Result<&str> {
+    member
+        .get_str(RS_ATTR_MEMBER_HOST)
+        .context(ConfigError::Attribute(RS_ATTR_MEMBER_HOST))
+}
+
+/// Lookup the number of `votes` of a Replica Set member configuration.
+///
+/// Members without an explicit number of votes have one vote.
+pub fn member_votes(member: &Document) -> i32 {
+    match member.get(RS_ATTR_MEMBER_VOTES) {
+        Some(Bson::Int32(votes)) => *votes,
+        Some(Bson::Int64(votes)) => *votes as i32,
+        Some(Bson::Double(votes)) => *votes as i32,
+        _ => 1,
+    }
+}
+
+/// Errors encountered while handling the Replica Set configuration.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// Attribute is missing on has unexpected type.
+    #[error("attribute '{0}' is missing on has unexpected type")]
+    // (attribute,)
+    Attribute(&'static str),
+
+    /// Unable to fetch the replica set configuration.
+    #[error("unable to fetch the replica set configuration")]
+    GetFailed,
+
+    /// Invalid replica set configuration.
+    #[error("invalid replica set configuration")]
+    Invalid,
+
+    /// Unable to reconfigure the replica set.
+    #[error("unable to reconfigure the replica set")]
+    ReconfigFailed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::member_index;
+    use crate::replicaset::actions::fixtures::config;
+    use crate::replicaset::actions::fixtures::member;
+
+    #[test]
+    fn member_by_host() {
+        let rs = config(1, vec![member(0), member(2)]);
+        let index = member_index(&rs, Some("mongo-2:27017"), None).unwrap();
+        assert_eq!(index, Some(1));
+    }
+
+    #[test]
+    fn member_by_id() {
+        let rs = config(1, vec![member(0), member(2)]);
+        let index = member_index(&rs, None, Some(0)).unwrap();
+        assert_eq!(index, Some(0));
+    }
+
+    #[test]
+    fn member_selectors_must_match() {
+        let rs = config(1, vec![member(0), member(2)]);
+        let index = member_index(&rs, Some("mongo-2:27017"), Some(0)).unwrap();
+        assert_eq!(index, None);
+    }
+}
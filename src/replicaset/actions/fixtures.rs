@@ -0,0 +1,56 @@
+//! Replica set documents shared by the action tests.
+use mongodb::bson::doc;
+use mongodb::bson::Document;
+
+use super::config;
+
+/// Replica set configuration with the given version and members.
+pub fn config(version: i32, members: Vec<Document>) -> Document {
+    doc! {
+        "_id": "rs0",
+        "version": version,
+        "members": members,
+    }
+}
+
+/// Configuration of member `id`, hosted on `mongo-{id}:27017`.
+pub fn member(id: i32) -> Document {
+    doc! {"_id": id, "host": format!("mongo-{}:27017", id)}
+}
+
+/// Configuration of member `id` without votes or priority.
+pub fn non_voter(id: i32) -> Document {
+    let mut member = member(id);
+    member.insert("votes", 0);
+    member.insert("priority", 0);
+    member
+}
+
+/// Configuration of member `id` with all defaults set explicitly, as returned by the server.
+pub fn voter(id: i32) -> Document {
+    let mut member = member(id);
+    member.insert("arbiterOnly", false);
+    member.insert("hidden", false);
+    member.insert("priority", 1.0);
+    member.insert("votes", 1);
+    member
+}
+
+/// Replica set status with members numbered by position and the given `(state, health)`.
+pub fn status(members: &[(i32, f64)]) -> Document {
+    let members: Vec<_> = members
+        .iter()
+        .enumerate()
+        .map(|(id, (state, health))| doc! {"_id": id as i32, "state": state, "health": health})
+        .collect();
+    doc! {"set": "rs0", "members": members}
+}
+
+/// IDs of the members in a replica set configuration.
+pub fn ids(rs: &Document) -> Vec<i32> {
+    config::members(rs)
+        .unwrap()
+        .into_iter()
+        .map(|member| config::member_id(member).unwrap())
+        .collect()
+}
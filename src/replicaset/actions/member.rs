@@ -0,0 +1,301 @@
+//! Options and validation for Replica Set member configurations.
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use mongodb::bson::Document;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::config;
+
+/// Maximum number of voting members in a Replica Set.
+pub const MAX_VOTERS: usize = 7;
+
+/// Maximum priority of a Replica Set member.
+const MAX_PRIORITY: f64 = 1000.0;
+
+/// First MongoDB major version using `secondaryDelaySecs` in place of `slaveDelay`.
+const SECONDARY_DELAY_SECS_SINCE: i32 = 5;
+
+/// Optional attributes of Replica Set members.
+///
+/// Options that are not set are left to the server defaults (or current value).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemberOptions {
+    /// Build indexes on the member (can only be unset on members with priority 0).
+    #[serde(default, alias = "buildIndexes")]
+    pub build_indexes: Option<bool>,
+
+    /// Hide the member from clients (can only be set on members with priority 0).
+    #[serde(default)]
+    pub hidden: Option<bool>,
+
+    /// Election priority of the member, between 0 and 1000.
+    #[serde(default)]
+    pub priority: Option<f64>,
+
+    /// Number of seconds the member should lag behind the primary.
+    ///
+    /// Sent to the server as `secondaryDelaySecs` (MongoDB 5.0+) or `slaveDelay`.
+    #[serde(
+        default,
+        alias = "secondaryDelaySecs",
+        alias = "secondary_delay_secs",
+        alias = "slaveDelay"
+    )]
+    pub delay: Option<i64>,
+
+    /// Member tags for custom write concerns and read preferences.
+    #[serde(default)]
+    pub tags: Option<BTreeMap<String, String>>,
+
+    /// Number of votes the member has in elections (0 or 1).
+    #[serde(default)]
+    pub votes: Option<i32>,
+}
+
+impl MemberOptions {
+    /// Set the options on a member configuration document.
+    ///
+    /// The `major_version` of the server is used to select version-dependent attributes.
+    pub fn apply(&self, member: &mut Document, major_version: i32) {
+        if let Some(build_indexes) = self.build_indexes {
+            member.insert("buildIndexes", build_indexes);
+        }
+        if let Some(hidden) = self.hidden {
+            member.insert("hidden", hidden);
+        }
+        if let Some(priority) = self.priority {
+            member.insert("priority", priority);
+        }
+        if let Some(delay) = self.delay {
+            member.insert(delay_attribute(major_version), delay);
+        }
+        if let Some(tags) = &self.tags {
+            let tags: Document = tags
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone().into()))
+                .collect();
+            member.insert("tags", tags);
+        }
+        if let Some(votes) = self.votes {
+            member.insert(config::RS_ATTR_MEMBER_VOTES, votes);
+        }
+    }
+}
+
+/// Validate a member configuration document against MongoDB rules.
+pub fn validate(member: &Document) -> Result<()> {
+    let priority = member_priority(member);
+    if !(0.0..=MAX_PRIORITY).contains(&priority) {
+        anyhow::bail!(MemberError::InvalidPriority(priority));
+    }
+    let votes = config::member_votes(member);
+    if votes != 0 && votes != 1 {
+        anyhow::bail!(MemberError::InvalidVotes(votes));
+    }
+
+    // Members that can't become primary must have priority 0.
+    if priority > 0.0 {
+        if votes == 0 {
+            anyhow::bail!(MemberError::PriorityWithoutVotes);
+        }
+        if member.get_bool("hidden").unwrap_or(false) {
+            anyhow::bail!(MemberError::PriorityWithHidden);
+        }
+        if !member.get_bool("buildIndexes").unwrap_or(true) {
+            anyhow::bail!(MemberError::PriorityWithoutIndexes);
+        }
+        if member_delay(member) > 0 {
+            anyhow::bail!(MemberError::PriorityWithDelay);
+        }
+    }
+    if member_delay(member) < 0 {
+        anyhow::bail!(MemberError::InvalidDelay(member_delay(member)));
+    }
+    Ok(())
+}
+
+/// Validate the number of voting members in a Replica Set configuration.
+pub fn validate_voters(rs: &Document) -> Result<()> {
+    let voters = config::members(rs)?
+        .into_iter()
+        .filter(|member| config::member_votes(member) > 0)
+        .count();
+    if voters > MAX_VOTERS {
+        anyhow::bail!(MemberError::TooManyVoters(voters));
+    }
+    Ok(())
+}
+
+/// Name of the member attribute to configure replication delay with.
+pub fn delay_attribute(major_version: i32) -> &'static str {
+    if major_version >= SECONDARY_DELAY_SECS_SINCE {
+        "secondaryDelaySecs"
+    } else {
+        "slaveDelay"
+    }
+}
+
+/// Extract the server major version from the output of the `buildInfo` command.
+pub fn major_version(build_info: &Document) -> Result<i32> {
+    let (major, _) = version(build_info)?;
+    Ok(major)
+}
+
+/// Extract the server `(major, minor)` version from the output of the `buildInfo` command.
+pub fn version(build_info: &Document) -> Result<(i32, i32)> {
+    let version = build_info
+        .get_array("versionArray")
+        .ok()
+        .and_then(|version| {
+            let major = version.first()?.as_i32()?;
+            let minor = version.get(1)?.as_i32()?;
+            Some((major, minor))
+        });
+    if let Some(version) = version {
+        return Ok(version);
+    }
+    let version = build_info
+        .get_str("version")
+        .ok()
+        .and_then(|version| {
+            let mut parts = version.split('.');
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            Some((major, minor))
+        })
+        .ok_or(MemberError::UnknownVersion)?;
+    Ok(version)
+}
+
+/// Lookup the priority of a member, accounting for the server default.
+fn member_priority(member: &Document) -> f64 {
+    match member.get("priority") {
+        Some(mongodb::bson::Bson::Double(priority)) => *priority,
+        Some(mongodb::bson::Bson::Int32(priority)) => f64::from(*priority),
+        Some(mongodb::bson::Bson::Int64(priority)) => *priority as f64,
+        _ => 1.0,
+    }
+}
+
+/// Lookup the replication delay of a member, regardless of the attribute name.
+fn member_delay(member: &Document) -> i64 {
+    ["secondaryDelaySecs", "slaveDelay"]
+        .iter()
+        .find_map(|attr| match member.get(attr) {
+            Some(mongodb::bson::Bson::Int32(delay)) => Some(i64::from(*delay)),
+            Some(mongodb::bson::Bson::Int64(delay)) => Some(*delay),
+            Some(mongodb::bson::Bson::Double(delay)) => Some(*delay as i64),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+/// Errors detected while validating Replica Set member configurations.
+#[derive(Debug, thiserror::Error)]
+pub enum MemberError {
+    /// Member replication delay must not be negative.
+    #[error("member replication delay must not be negative, got {0}")]
+    InvalidDelay(i64),
+
+    /// Member priority must be between 0 and 1000.
+    #[error("member priority must be between 0 and 1000, got {0}")]
+    InvalidPriority(f64),
+
+    /// Member votes must be 0 or 1.
+    #[error("member votes must be 0 or 1, got {0}")]
+    InvalidVotes(i32),
+
+    /// Delayed members must have priority 0.
+    #[error("delayed members must have priority 0")]
+    PriorityWithDelay,
+
+    /// Hidden members must have priority 0.
+    #[error("hidden members must have priority 0")]
+    PriorityWithHidden,
+
+    /// Members that do not build indexes must have priority 0.
+    #[error("members that do not build indexes must have priority 0")]
+    PriorityWithoutIndexes,
+
+    /// Non-voting members must have priority 0.
+    #[error("non-voting members must have priority 0")]
+    PriorityWithoutVotes,
+
+    /// Replica sets can have at most 7 voting members.
+    #[error("replica sets can have at most 7 voting members, got {0}")]
+    TooManyVoters(usize),
+
+    /// Unable to determine the MongoDB server version.
+    #[error("unable to determine the MongoDB server version")]
+    UnknownVersion,
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+
+    use super::validate;
+    use super::validate_voters;
+    use super::version;
+    use super::MemberError;
+    use super::MemberOptions;
+
+    #[test]
+    fn apply_delay_by_version() {
+        let options = MemberOptions {
+            delay: Some(3600),
+            priority: Some(0.0),
+            ..Default::default()
+        };
+        let mut member = doc! {"_id": 1, "host": "mongo-1:27017"};
+        options.apply(&mut member, 4);
+        assert_eq!(member.get_i64("slaveDelay"), Ok(3600));
+        let mut member = doc! {"_id": 1, "host": "mongo-1:27017"};
+        options.apply(&mut member, 6);
+        assert_eq!(member.get_i64("secondaryDelaySecs"), Ok(3600));
+        validate(&member).unwrap();
+    }
+
+    #[test]
+    fn hidden_member_needs_priority_zero() {
+        let member = doc! {"_id": 1, "host": "mongo-1:27017", "hidden": true};
+        let error = validate(&member).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(MemberError::PriorityWithHidden)
+        ));
+    }
+
+    #[test]
+    fn invalid_votes() {
+        let member = doc! {"_id": 1, "host": "mongo-1:27017", "votes": 2};
+        let error = validate(&member).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(MemberError::InvalidVotes(2))
+        ));
+    }
+
+    #[test]
+    fn too_many_voters() {
+        let members: Vec<_> = (0..8)
+            .map(|id| doc! {"_id": id, "host": format!("mongo-{}:27017", id)})
+            .collect();
+        let rs = doc! {"_id": "rs0", "version": 1, "members": members};
+        let error = validate_voters(&rs).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(MemberError::TooManyVoters(8))
+        ));
+    }
+
+    #[test]
+    fn version_from_build_info() {
+        let build_info = doc! {"version": "4.4.18", "versionArray": [4, 4, 18, 0]};
+        assert_eq!(version(&build_info).unwrap(), (4, 4));
+        let build_info = doc! {"version": "7.0.2"};
+        assert_eq!(version(&build_info).unwrap(), (7, 0));
+    }
+}
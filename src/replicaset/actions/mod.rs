@@ -1,3 +1,11 @@
 //! Collection of Agent Action implementations for MongoDB Replica Sets.
 
 pub mod cluster;
+pub mod node;
+
+mod config;
+mod member;
+mod reconfig;
+
+#[cfg(test)]
+mod fixtures;
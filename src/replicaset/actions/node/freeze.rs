@@ -0,0 +1,98 @@
+//! Agent action to prevent the node from becoming primary for some time.
+//!
+//! The action will freeze the node with [`replSetFreeze`] so it does not seek election.
+//! Freezing a node for `0` seconds unfreezes it.
+//! The primary can't be frozen (step it down first).
+//!
+//! The remaining freeze window is reported in the node attributes until it expires.
+//! MongoDB does not report freezes so only those requested by the running agent are tracked.
+//!
+//! ## Arguments
+//!
+//! Arguments are required unless otherwise noted.
+//!
+//! The action has the following arguments:
+//!
+//! - `seconds`: Seconds the node is not eligible to become primary for (`0` to unfreeze).
+//!
+//! [`replSetFreeze`]: https://www.mongodb.com/docs/manual/reference/command/replSetFreeze/
+use std::future::IntoFuture;
+use std::time::Duration;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use opentelemetry::trace::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+use crate::constants::ACTION_PREFIX;
+use crate::constants::CMD_REPL_SET_FREEZE;
+use crate::constants::DB_ADMIN;
+use crate::metrics::observe_mongodb_op;
+
+/// Prevent the node from becoming primary for some time.
+#[derive(Debug)]
+pub struct Freeze;
+
+impl Freeze {
+    /// Registration metadata for the node freeze action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/node.freeze", ACTION_PREFIX);
+        ActionMetadata::build(kind, Freeze).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for Freeze {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args: FreezeArgs =
+            serde_json::from_value(action.args.clone()).context(FreezeError::InvalidArgs)?;
+        let client = crate::client::global();
+
+        slog::info!(context.logger, "Freezing node"; "seconds" => args.seconds);
+        let admin = client.database(DB_ADMIN);
+        let command = mongodb::bson::doc! {CMD_REPL_SET_FREEZE: args.seconds};
+        let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_FREEZE);
+        let (err_count, _timer) = observe_mongodb_op(CMD_REPL_SET_FREEZE);
+        admin
+            .run_command(command)
+            .into_future()
+            .count_on_err(err_count)
+            .trace_on_err_with_status()
+            .with_context(trace)
+            .await
+            .context(FreezeError::Failed)?;
+
+        crate::replicaset::state::freeze(Duration::from_secs(args.seconds.into()));
+        let changes = Changes::to(ActionExecutionPhase::Done);
+        Ok(changes)
+    }
+}
+
+/// Arguments to freeze the node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FreezeArgs {
+    /// Seconds the node is not eligible to become primary for (`0` to unfreeze).
+    pub seconds: u32,
+}
+
+/// Errors encountered while freezing the node.
+#[derive(Debug, thiserror::Error)]
+pub enum FreezeError {
+    /// Unable to freeze the node.
+    #[error("unable to freeze the node")]
+    Failed,
+
+    /// Arguments provided to the [`Freeze`] action are not valid.
+    #[error("arguments provided to the freeze action are not valid")]
+    InvalidArgs,
+}
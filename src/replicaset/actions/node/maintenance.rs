@@ -0,0 +1,157 @@
+//! Agent actions to move secondaries in and out of maintenance mode.
+//!
+//! Nodes in maintenance mode enter the `RECOVERING` state so clients stop reading from them
+//! while expensive operations (compaction, index rebuilds, ...) run.
+//! Maintenance mode is managed with [`replSetMaintenance`] and the primary can't enter it.
+//!
+//! The server counts requests to enter maintenance mode from all clients and the node
+//! remains `RECOVERING` until an equal number of requests to leave maintenance mode are made.
+//! The agent records its own request so it never undoes requests made by other clients:
+//!
+//! - Entering maintenance mode does nothing if the agent already requested it.
+//! - Leaving maintenance mode only undoes the request made by the agent, if any.
+//!
+//! Nodes in maintenance mode because of the agent are reported as unavailable rather than
+//! unhealthy. The agent request is tracked in memory and is forgotten if the agent restarts.
+//!
+//! ## Arguments
+//!
+//! The actions take no arguments.
+//!
+//! [`replSetMaintenance`]: https://www.mongodb.com/docs/manual/reference/command/replSetMaintenance/
+use std::future::IntoFuture;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::Client;
+use opentelemetry::trace::FutureExt;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+use crate::constants::MemberState;
+use crate::constants::ACTION_PREFIX;
+use crate::constants::CMD_REPL_SET_MAINTENANCE;
+use crate::constants::DB_ADMIN;
+use crate::metrics::observe_mongodb_op;
+
+/// Move the node into maintenance mode.
+#[derive(Debug)]
+pub struct MaintenanceEnter;
+
+impl MaintenanceEnter {
+    /// Registration metadata for the enter maintenance action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/node.maintenance.enter", ACTION_PREFIX);
+        ActionMetadata::build(kind, MaintenanceEnter).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for MaintenanceEnter {
+    async fn invoke(&self, context: &Context, _: &ActionExecution) -> Result<Changes> {
+        let client = crate::client::global();
+        let (state, requests) = maintenance_status(&client).await?;
+        if state == MemberState::Primary as i32 {
+            anyhow::bail!(MaintenanceError::Primary);
+        }
+        if requests > 0 && crate::replicaset::state::maintenance_requested() {
+            slog::info!(context.logger, "Node is already in maintenance mode");
+            return Ok(Changes::to(ActionExecutionPhase::Done));
+        }
+
+        slog::info!(context.logger, "Node entering maintenance mode"; "requests" => requests);
+        maintenance(&client, true).await?;
+        crate::replicaset::state::maintenance(true);
+        let changes = Changes::to(ActionExecutionPhase::Done);
+        Ok(changes)
+    }
+}
+
+/// Move the node out of maintenance mode.
+#[derive(Debug)]
+pub struct MaintenanceLeave;
+
+impl MaintenanceLeave {
+    /// Registration metadata for the leave maintenance action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/node.maintenance.leave", ACTION_PREFIX);
+        ActionMetadata::build(kind, MaintenanceLeave).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for MaintenanceLeave {
+    async fn invoke(&self, context: &Context, _: &ActionExecution) -> Result<Changes> {
+        let client = crate::client::global();
+        if !crate::replicaset::state::maintenance_requested() {
+            slog::info!(
+                context.logger,
+                "Node was not put in maintenance mode by the agent"
+            );
+            return Ok(Changes::to(ActionExecutionPhase::Done));
+        }
+
+        // The server forgets maintenance requests when it restarts.
+        let (_, requests) = maintenance_status(&client).await?;
+        if requests > 0 {
+            slog::info!(context.logger, "Node leaving maintenance mode"; "requests" => requests);
+            maintenance(&client, false).await?;
+        }
+        crate::replicaset::state::maintenance(false);
+        Ok(Changes::to(ActionExecutionPhase::Done))
+    }
+}
+
+/// Errors encountered while changing maintenance mode.
+#[derive(Debug, thiserror::Error)]
+pub enum MaintenanceError {
+    /// Unable to change the node maintenance mode.
+    #[error("unable to change the node maintenance mode")]
+    Failed,
+
+    /// The primary can't enter maintenance mode (step it down first).
+    #[error("the primary can't enter maintenance mode (step it down first)")]
+    Primary,
+
+    /// Replica set status does not report the state of the node.
+    #[error("replica set status does not report the state of the node")]
+    StatusInvalid,
+}
+
+/// Lookup the node state and the number of pending requests to enter maintenance mode.
+async fn maintenance_status(client: &Client) -> Result<(i32, i32)> {
+    let status = crate::client::admin::replica_set_status(client)
+        .await
+        .context(MaintenanceError::Failed)?;
+    let state = status
+        .get_i32("myState")
+        .context(MaintenanceError::StatusInvalid)?;
+    let requests = crate::client::admin::maintenance_requests(&status);
+    Ok((state, requests))
+}
+
+/// Run the [`replSetMaintenance`] command to enter or leave maintenance mode.
+///
+/// [`replSetMaintenance`]: https://www.mongodb.com/docs/manual/reference/command/replSetMaintenance/
+async fn maintenance(client: &Client, enable: bool) -> Result<()> {
+    let admin = client.database(DB_ADMIN);
+    let command = mongodb::bson::doc! {CMD_REPL_SET_MAINTENANCE: enable};
+    let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_MAINTENANCE);
+    let (err_count, _timer) = observe_mongodb_op(CMD_REPL_SET_MAINTENANCE);
+    admin
+        .run_command(command)
+        .into_future()
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+        .context(MaintenanceError::Failed)?;
+    Ok(())
+}
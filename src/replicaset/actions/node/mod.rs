@@ -0,0 +1,10 @@
+//! Implementation of node management agent actions.
+
+mod freeze;
+mod maintenance;
+mod resize_oplog;
+
+pub use self::freeze::Freeze;
+pub use self::maintenance::MaintenanceEnter;
+pub use self::maintenance::MaintenanceLeave;
+pub use self::resize_oplog::ResizeOplog;
@@ -0,0 +1,312 @@
+//! Agent action to change the maximum size of the node's oplog.
+//!
+//! The action will resize the oplog with [`replSetResizeOplog`].
+//! Resizing the oplog only affects the node the action runs on.
+//!
+//! The action completes once [`collStats`] for the `local.oplog.rs` collection
+//! reports the new maximum size.
+//! The action fails if the new size is still not reported after a limited number of checks
+//! (for example because the oplog was resized again by someone else).
+//! If the oplog already has the requested size (and minimum retention period, when given)
+//! the action completes without changes.
+//!
+//! ## Arguments
+//!
+//! Arguments are required unless otherwise noted.
+//!
+//! The action has the following arguments:
+//!
+//! - `sizeMB`: New maximum size of the oplog in megabytes (between 990 and 1 petabyte).
+//! - `minRetentionHours` [OPTIONAL]: Minimum number of hours to retain oplog entries for
+//!   (requires MongoDB 4.4 or later).
+//!
+//! [`collStats`]: https://www.mongodb.com/docs/manual/reference/command/collStats/
+//! [`replSetResizeOplog`]: https://www.mongodb.com/docs/manual/reference/command/replSetResizeOplog/
+use std::future::IntoFuture;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Document;
+use mongodb::Client;
+use opentelemetry::trace::FutureExt;
+use serde::Deserialize;
+use serde::Serialize;
+
+use replisdk::agent::framework::actions::ActionHandler;
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::framework::actions::ActionMetadata;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureStdErrExt;
+
+use crate::constants::ACTION_PREFIX;
+use crate::constants::CMD_REPL_SET_RESIZE_OPLOG;
+use crate::constants::DB_ADMIN;
+use crate::metrics::observe_mongodb_op;
+use crate::replicaset::actions::member;
+
+/// First MongoDB version supporting a minimum oplog retention period.
+const MIN_RETENTION_HOURS_SINCE: (i32, i32) = (4, 4);
+
+/// Maximum size of the oplog accepted by MongoDB (1 petabyte), in megabytes.
+const MAX_SIZE_MB: u64 = 1024 * 1024 * 1024;
+
+/// Minimum size of the oplog accepted by MongoDB, in megabytes.
+const MIN_SIZE_MB: u64 = 990;
+
+/// Maximum number of times the oplog size is checked after the resize was requested.
+const MAX_SIZE_CHECKS: u64 = 10;
+
+/// Name of the action payload attribute recording the number of oplog size checks.
+///
+/// The attribute is only set once the resize was requested.
+const PAYLOAD_CHECKS: &str = "checks";
+
+/// Change the maximum size of the node's oplog.
+#[derive(Debug)]
+pub struct ResizeOplog;
+
+impl ResizeOplog {
+    /// Registration metadata for the node resize oplog action.
+    pub fn metadata() -> ActionMetadata {
+        let kind = format!("{}/node.resize-oplog", ACTION_PREFIX);
+        ActionMetadata::build(kind, ResizeOplog).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ActionHandler for ResizeOplog {
+    async fn invoke(&self, context: &Context, action: &ActionExecution) -> Result<Changes> {
+        let args: ResizeOplogArgs =
+            serde_json::from_value(action.args.clone()).context(ResizeOplogError::InvalidArgs)?;
+        let target = target_bytes(args.size_mb)?;
+        let client = crate::client::global();
+        let checks = action
+            .state
+            .payload
+            .as_ref()
+            .and_then(|payload| payload.get(PAYLOAD_CHECKS))
+            .and_then(|checks| checks.as_u64());
+
+        // Check if the oplog already has the requested size.
+        let current = crate::replicaset::info::oplog::stats(&client)
+            .await
+            .context(ResizeOplogError::Failed)?
+            .max_size;
+        if let Some(checks) = checks {
+            // The retention period is applied by the resize command so only wait on the size.
+            return wait_for_size(target, current, checks).map(progress);
+        }
+        if current == target && retention_applied(&client, args.min_retention_hours).await? {
+            return Ok(Changes::to(ActionExecutionPhase::Done));
+        }
+
+        // Resize the oplog.
+        let mut command = mongodb::bson::doc! {
+            CMD_REPL_SET_RESIZE_OPLOG: 1,
+            "size": args.size_mb as f64,
+        };
+        if let Some(hours) = args.min_retention_hours {
+            let build_info = crate::client::admin::build_info(&client)
+                .await
+                .context(ResizeOplogError::Failed)?;
+            if member::version(&build_info)? < MIN_RETENTION_HOURS_SINCE {
+                anyhow::bail!(ResizeOplogError::MinRetentionNotSupported);
+            }
+            command.insert("minRetentionHours", hours);
+        }
+        slog::info!(
+            context.logger, "Resizing oplog";
+            "current" => current, "size_mb" => args.size_mb,
+            "min_retention_hours" => ?args.min_retention_hours,
+        );
+        let admin = client.database(DB_ADMIN);
+        let trace = crate::trace::mongodb_client_context(CMD_REPL_SET_RESIZE_OPLOG);
+        let (err_count, _timer) = observe_mongodb_op(CMD_REPL_SET_RESIZE_OPLOG);
+        admin
+            .run_command(command)
+            .into_future()
+            .count_on_err(err_count)
+            .trace_on_err_with_status()
+            .with_context(trace)
+            .await
+            .context(ResizeOplogError::Failed)?;
+
+        // Wait for the new size to be reported.
+        let current = crate::replicaset::info::oplog::stats(&client)
+            .await
+            .context(ResizeOplogError::Failed)?
+            .max_size;
+        wait_for_size(target, current, 0).map(progress)
+    }
+}
+
+/// Check if the oplog has the `target` size after the resize was requested.
+///
+/// Returns `None` once the oplog has the target size, otherwise the number of checks so far.
+/// Fails once the size was checked too many times without matching the target.
+fn wait_for_size(target: i64, current: i64, checks: u64) -> Result<Option<u64>> {
+    if current == target {
+        return Ok(None);
+    }
+    let checks = checks + 1;
+    if checks >= MAX_SIZE_CHECKS {
+        anyhow::bail!(ResizeOplogError::SizeNotApplied {
+            expected: target,
+            observed: current,
+        });
+    }
+    Ok(Some(checks))
+}
+
+/// Report action progress based on the result of [`wait_for_size`].
+fn progress(checks: Option<u64>) -> Changes {
+    match checks {
+        None => Changes::to(ActionExecutionPhase::Done),
+        Some(checks) => {
+            let payload = serde_json::json!({ PAYLOAD_CHECKS: checks });
+            Changes::to(ActionExecutionPhase::Running).payload(payload)
+        }
+    }
+}
+
+/// Validate the requested oplog size and convert it to bytes.
+fn target_bytes(size_mb: u64) -> Result<i64> {
+    if size_mb < MIN_SIZE_MB {
+        anyhow::bail!(ResizeOplogError::TooSmall(size_mb));
+    }
+    if size_mb > MAX_SIZE_MB {
+        anyhow::bail!(ResizeOplogError::TooLarge(size_mb));
+    }
+    size_mb
+        .checked_mul(1024 * 1024)
+        .and_then(|bytes| i64::try_from(bytes).ok())
+        .ok_or_else(|| anyhow::anyhow!(ResizeOplogError::TooLarge(size_mb)))
+}
+
+/// Check if the node already retains oplog entries for the requested minimum period.
+///
+/// Nothing to check if no minimum retention period is requested.
+async fn retention_applied(client: &Client, hours: Option<f64>) -> Result<bool> {
+    let hours = match hours {
+        None => return Ok(true),
+        Some(hours) => hours,
+    };
+    let status = crate::client::admin::server_status(client)
+        .await
+        .context(ResizeOplogError::Failed)?;
+    Ok(retention_matches(&status, hours))
+}
+
+/// Check if the `serverStatus` output reports the given minimum oplog retention period.
+fn retention_matches(status: &Document, hours: f64) -> bool {
+    status
+        .get_document("oplogTruncation")
+        .and_then(|truncation| truncation.get_f64("oplogMinRetentionHours"))
+        .map(|current| current == hours)
+        .unwrap_or(false)
+}
+
+/// Arguments to resize the oplog.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResizeOplogArgs {
+    /// Minimum number of hours to retain oplog entries for.
+    #[serde(default, alias = "minRetentionHours")]
+    pub min_retention_hours: Option<f64>,
+
+    /// New maximum size of the oplog in megabytes.
+    #[serde(alias = "sizeMB")]
+    pub size_mb: u64,
+}
+
+/// Errors encountered while resizing the oplog.
+#[derive(Debug, thiserror::Error)]
+pub enum ResizeOplogError {
+    /// Unable to resize the oplog.
+    #[error("unable to resize the oplog")]
+    Failed,
+
+    /// Arguments provided to the [`ResizeOplog`] action are not valid.
+    #[error("arguments provided to the resize oplog action are not valid")]
+    InvalidArgs,
+
+    /// A minimum oplog retention period requires MongoDB 4.4 or later.
+    #[error("a minimum oplog retention period requires MongoDB 4.4 or later")]
+    MinRetentionNotSupported,
+
+    /// The oplog maximum size did not change to the requested size.
+    #[error(
+        "the oplog maximum size is {observed} bytes instead of the requested {expected} bytes"
+    )]
+    SizeNotApplied { expected: i64, observed: i64 },
+
+    /// The requested oplog size is above the 1PB maximum.
+    #[error("the requested oplog size of {0}MB is above the 1PB maximum")]
+    // (size_mb,)
+    TooLarge(u64),
+
+    /// The requested oplog size is below the 990MB minimum.
+    #[error("the requested oplog size of {0}MB is below the 990MB minimum")]
+    // (size_mb,)
+    TooSmall(u64),
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+
+    use super::retention_matches;
+    use super::target_bytes;
+    use super::wait_for_size;
+    use super::ResizeOplogError;
+    use super::MAX_SIZE_CHECKS;
+
+    #[test]
+    fn target_size_bounds() {
+        assert_eq!(target_bytes(990).unwrap(), 990 * 1024 * 1024);
+        let error = target_bytes(989).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ResizeOplogError::TooSmall(989))
+        ));
+        let error = target_bytes(u64::MAX).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ResizeOplogError::TooLarge(u64::MAX))
+        ));
+    }
+
+    #[test]
+    fn retention_compared() {
+        let status = doc! {"oplogTruncation": {"oplogMinRetentionHours": 24.0}};
+        assert!(retention_matches(&status, 24.0));
+        assert!(!retention_matches(&status, 12.0));
+        assert!(!retention_matches(&doc! {"ok": 1.0}, 24.0));
+    }
+
+    #[test]
+    fn wait_for_size_complete() {
+        assert_eq!(wait_for_size(1024, 1024, 0).unwrap(), None);
+        assert_eq!(wait_for_size(1024, 1024, 3).unwrap(), None);
+    }
+
+    #[test]
+    fn wait_for_size_running() {
+        assert_eq!(wait_for_size(1024, 512, 0).unwrap(), Some(1));
+        assert_eq!(wait_for_size(1024, 512, 3).unwrap(), Some(4));
+    }
+
+    #[test]
+    fn wait_for_size_gives_up() {
+        let error = wait_for_size(1024, 512, MAX_SIZE_CHECKS - 1).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ResizeOplogError::SizeNotApplied {
+                expected: 1024,
+                observed: 512
+            })
+        ));
+    }
+}
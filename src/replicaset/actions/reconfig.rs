@@ -0,0 +1,399 @@
+//! Safely apply Replica Set reconfigurations that span multiple steps.
+//!
+//! Starting with MongoDB 4.4 the server only accepts reconfigurations that add or remove
+//! at most one voting member at a time and refuses new reconfigurations until the current
+//! configuration is committed (propagated to a majority of voting members).
+//!
+//! Actions describe the desired list of members and the [`Reconfiguration`] engine computes
+//! and applies a sequence of intermediate configurations, each changing at most one voter.
+//! Applying changes one voter at a time is also safe on earlier MongoDB versions.
+//!
+//! The engine never blocks waiting for a configuration to be committed.
+//! Instead the desired list of members is stored in the action payload and the action
+//! is moved to the [`ActionExecutionPhase::Running`] phase.
+//! The agent invokes running actions again later and the engine picks up where it left off
+//! until the configuration matches the desired members and is committed.
+use std::collections::BTreeSet;
+
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use mongodb::Client;
+
+use replisdk::agent::framework::actions::ActionHandlerChanges as Changes;
+use replisdk::agent::models::ActionExecution;
+use replisdk::agent::models::ActionExecutionPhase;
+use replisdk::context::Context;
+
+use super::config;
+use super::member::MAX_VOTERS;
+
+/// Name of the action payload attribute storing the desired list of members.
+const PAYLOAD_MEMBERS: &str = "members";
+
+/// Multi-step reconfiguration of the Replica Set towards a desired list of members.
+#[derive(Debug)]
+pub struct Reconfiguration {
+    client: Client,
+    committed: bool,
+    desired: Option<Vec<Document>>,
+    rs: Document,
+}
+
+impl Reconfiguration {
+    /// Load the current Replica Set configuration and any in-progress reconfiguration.
+    pub async fn load(client: &Client, action: &ActionExecution) -> Result<Reconfiguration> {
+        let (rs, committed) = config::get_with_commitment(client).await?;
+        let desired = match &action.state.payload {
+            None => None,
+            Some(payload) => decode_payload(payload)?,
+        };
+        Ok(Reconfiguration {
+            client: client.clone(),
+            committed,
+            desired,
+            rs,
+        })
+    }
+
+    /// Current Replica Set configuration.
+    pub fn config(&self) -> &Document {
+        &self.rs
+    }
+
+    /// Check if the action has already started a reconfiguration.
+    ///
+    /// In-progress reconfigurations must be resumed and not started again.
+    pub fn in_progress(&self) -> bool {
+        self.desired.is_some()
+    }
+
+    /// Start reconfiguring the Replica Set towards the given list of members.
+    pub async fn start(mut self, context: &Context, members: Vec<Document>) -> Result<Changes> {
+        self.desired = Some(members);
+        self.step(context).await
+    }
+
+    /// Continue an in-progress reconfiguration from the current Replica Set configuration.
+    pub async fn resume(self, context: &Context) -> Result<Changes> {
+        if !self.in_progress() {
+            anyhow::bail!(ReconfigError::NotStarted);
+        }
+        self.step(context).await
+    }
+
+    /// Apply the next reconfiguration step, if possible, and report action progress.
+    async fn step(self, context: &Context) -> Result<Changes> {
+        let desired = self.desired.unwrap_or_default();
+        let payload = encode_payload(&desired);
+
+        // Wait for the current configuration to propagate before changing it again.
+        if !self.committed {
+            slog::debug!(
+                context.logger,
+                "Waiting for replica set configuration to be committed";
+                "version" => ?self.rs.get(config::RS_ATTR_VERSION),
+            );
+            return Ok(Changes::to(ActionExecutionPhase::Running).payload(payload));
+        }
+
+        // Check if the desired configuration was reached or apply the next step.
+        let rs = match next_config(&self.rs, &desired)? {
+            None => return Ok(Changes::to(ActionExecutionPhase::Done)),
+            Some(rs) => rs,
+        };
+        slog::info!(
+            context.logger, "Applying replica set reconfiguration step";
+            "version" => ?rs.get(config::RS_ATTR_VERSION),
+            "members" => ?rs.get(config::RS_ATTR_MEMBERS),
+        );
+        config::reconfig(&self.client, rs)
+            .await
+            .context(ReconfigError::StepFailed)?;
+        Ok(Changes::to(ActionExecutionPhase::Running).payload(payload))
+    }
+}
+
+/// Errors encountered while reconfiguring the Replica Set over multiple steps.
+#[derive(Debug, thiserror::Error)]
+pub enum ReconfigError {
+    /// Attempted to resume a reconfiguration that was never started.
+    #[error("attempted to resume a reconfiguration that was never started")]
+    NotStarted,
+
+    /// The reconfiguration state stored in the action payload is not valid.
+    #[error("the reconfiguration state stored in the action payload is not valid")]
+    PayloadInvalid,
+
+    /// Unable to apply a reconfiguration step.
+    #[error("unable to apply a reconfiguration step")]
+    StepFailed,
+}
+
+/// Decode the desired list of members from the action payload.
+///
+/// Payloads without a list of members are ignored and `None` is returned.
+fn decode_payload(payload: &serde_json::Value) -> Result<Option<Vec<Document>>> {
+    let members = match payload.get(PAYLOAD_MEMBERS) {
+        None => return Ok(None),
+        Some(members) => members,
+    };
+    let members = Bson::try_from(members.clone()).context(ReconfigError::PayloadInvalid)?;
+    let members = match members {
+        Bson::Array(members) => members,
+        _ => anyhow::bail!(ReconfigError::PayloadInvalid),
+    };
+    let members = members
+        .into_iter()
+        .map(|member| match member {
+            Bson::Document(member) => Ok(member),
+            _ => anyhow::bail!(ReconfigError::PayloadInvalid),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(members))
+}
+
+/// Encode the desired list of members to store in the action payload.
+fn encode_payload(members: &[Document]) -> serde_json::Value {
+    let members: Vec<_> = members
+        .iter()
+        .map(|member| Bson::Document(member.clone()).into_relaxed_extjson())
+        .collect();
+    serde_json::json!({ PAYLOAD_MEMBERS: members })
+}
+
+/// Compute the next configuration to apply to move towards the desired list of members.
+///
+/// Returns `None` if the current configuration already matches the desired members.
+///
+/// Changes to non-voting attributes are applied immediately while voting members
+/// are added, removed or have their votes changed one at a time.
+/// When multiple voters change, additions are applied first unless the replica set
+/// already has the maximum number of voters, in which case removals go first.
+fn next_config(rs: &Document, desired: &[Document]) -> Result<Option<Document>> {
+    let current = config::members(rs)?;
+    if members_match(&current, desired)? {
+        return Ok(None);
+    }
+
+    // Find voters that change between configurations and pick one to change.
+    let current_voters = voters(&current)?;
+    let desired_voters = voters(&desired.iter().collect::<Vec<_>>())?;
+    let added: Vec<i32> = desired_voters
+        .difference(&current_voters)
+        .copied()
+        .collect();
+    let removed: Vec<i32> = current_voters
+        .difference(&desired_voters)
+        .copied()
+        .collect();
+    let changed = added.len() + removed.len();
+    let chosen = if current_voters.len() < MAX_VOTERS || removed.is_empty() {
+        added.first().or_else(|| removed.first())
+    } else {
+        removed.first()
+    };
+    let allowed = |id: i32| {
+        let voter_change = added.contains(&id) || removed.contains(&id);
+        changed <= 1 || !voter_change || chosen == Some(&id)
+    };
+
+    // Build the list of members, preserving the current order for existing members.
+    let mut members = Vec::new();
+    for member in &current {
+        let id = config::member_id(member)?;
+        let target = find_member(desired, id)?;
+        let member = match (target, allowed(id)) {
+            (Some(target), true) => Some(target),
+            (None, true) => None,
+            (_, false) => Some(*member),
+        };
+        if let Some(member) = member {
+            members.push(Bson::Document(member.clone()));
+        }
+    }
+    for member in desired {
+        let id = config::member_id(member)?;
+        if find_member(current.iter().copied(), id)?.is_none() && allowed(id) {
+            members.push(Bson::Document(member.clone()));
+        }
+    }
+
+    let mut rs = rs.clone();
+    rs.insert(config::RS_ATTR_MEMBERS, members);
+    config::bump_version(&mut rs)?;
+    Ok(Some(rs))
+}
+
+/// Find a member by `_id` in a list of member configurations.
+fn find_member<'a, I>(members: I, id: i32) -> Result<Option<&'a Document>>
+where
+    I: IntoIterator<Item = &'a Document>,
+{
+    for member in members {
+        if config::member_id(member)? == id {
+            return Ok(Some(member));
+        }
+    }
+    Ok(None)
+}
+
+/// Check if the current members match the desired members.
+///
+/// Members match when they have the same `_id`s and all attributes of desired members
+/// have the same value in current members (the server fills in defaults for the others).
+fn members_match(current: &[&Document], desired: &[Document]) -> Result<bool> {
+    if current.len() != desired.len() {
+        return Ok(false);
+    }
+    for target in desired {
+        let id = config::member_id(target)?;
+        let member = match find_member(current.iter().copied(), id)? {
+            None => return Ok(false),
+            Some(member) => member,
+        };
+        let same = target.iter().all(|(key, value)| {
+            member
+                .get(key)
+                .map(|m| same_value(m, value))
+                .unwrap_or(false)
+        });
+        if !same {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Compare BSON values ignoring differences in numeric types.
+pub fn same_value(left: &Bson, right: &Bson) -> bool {
+    fn number(value: &Bson) -> Option<f64> {
+        match value {
+            Bson::Double(value) => Some(*value),
+            Bson::Int32(value) => Some(f64::from(*value)),
+            Bson::Int64(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+    match (left, right) {
+        (Bson::Document(left), Bson::Document(right)) => {
+            left.len() == right.len()
+                && left.iter().all(|(key, value)| {
+                    right
+                        .get(key)
+                        .map(|other| same_value(value, other))
+                        .unwrap_or(false)
+                })
+        }
+        _ => match (number(left), number(right)) {
+            (Some(left), Some(right)) => left == right,
+            _ => left == right,
+        },
+    }
+}
+
+/// Collect the `_id`s of voting members.
+fn voters(members: &[&Document]) -> Result<BTreeSet<i32>> {
+    let mut voters = BTreeSet::new();
+    for member in members {
+        if config::member_votes(member) > 0 {
+            voters.insert(config::member_id(member)?);
+        }
+    }
+    Ok(voters)
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+
+    use super::decode_payload;
+    use super::encode_payload;
+    use super::next_config;
+    use crate::replicaset::actions::fixtures::config;
+    use crate::replicaset::actions::fixtures::ids;
+    use crate::replicaset::actions::fixtures::member;
+    use crate::replicaset::actions::fixtures::non_voter;
+    use crate::replicaset::actions::fixtures::voter;
+
+    #[test]
+    fn already_matching() {
+        let current = config(1, (0..2).map(voter).collect());
+        let desired = vec![
+            member(0),
+            doc! {"_id": 1, "host": "mongo-1:27017", "priority": 1},
+        ];
+        let next = next_config(&current, &desired).unwrap();
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn single_voter_added() {
+        let current = config(1, (0..2).map(voter).collect());
+        let desired = vec![voter(0), voter(1), member(2)];
+        let next = next_config(&current, &desired).unwrap().unwrap();
+        assert_eq!(ids(&next), vec![0, 1, 2]);
+        assert_eq!(next.get_i32("version").unwrap(), 2);
+    }
+
+    #[test]
+    fn voters_added_one_at_a_time() {
+        let current = config(1, (0..2).map(voter).collect());
+        let desired: Vec<_> = (0..4).map(voter).collect();
+        let next = next_config(&current, &desired).unwrap().unwrap();
+        assert_eq!(ids(&next), vec![0, 1, 2]);
+        let next = next_config(&next, &desired).unwrap().unwrap();
+        assert_eq!(ids(&next), vec![0, 1, 2, 3]);
+        assert_eq!(next.get_i32("version").unwrap(), 3);
+        assert_eq!(next_config(&next, &desired).unwrap(), None);
+    }
+
+    #[test]
+    fn voters_replaced_one_at_a_time() {
+        let current = config(1, (0..3).map(voter).collect());
+        let desired = vec![voter(0), voter(1), voter(3)];
+        let next = next_config(&current, &desired).unwrap().unwrap();
+        assert_eq!(ids(&next), vec![0, 1, 2, 3]);
+        let next = next_config(&next, &desired).unwrap().unwrap();
+        assert_eq!(ids(&next), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn removals_first_with_max_voters() {
+        let current = config(1, (0..7).map(voter).collect());
+        let mut desired: Vec<_> = (1..7).map(voter).collect();
+        desired.push(voter(7));
+        let next = next_config(&current, &desired).unwrap().unwrap();
+        assert_eq!(ids(&next), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn non_voters_change_together() {
+        let current = config(1, (0..3).map(voter).collect());
+        let mut desired = vec![voter(0), voter(1), voter(2)];
+        desired.push(non_voter(3));
+        desired.push(non_voter(4));
+        desired[2].insert("priority", 2.0);
+        let next = next_config(&current, &desired).unwrap().unwrap();
+        assert_eq!(ids(&next), vec![0, 1, 2, 3, 4]);
+        assert_eq!(next_config(&next, &desired).unwrap(), None);
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        let desired = vec![
+            voter(0),
+            doc! {"_id": 1, "host": "mongo-1:27017", "tags": {"dc": "a"}},
+        ];
+        let payload = encode_payload(&desired);
+        let decoded = decode_payload(&payload).unwrap();
+        assert_eq!(decoded, Some(desired));
+    }
+
+    #[test]
+    fn payload_without_members() {
+        let decoded = decode_payload(&serde_json::json!({"other": 1})).unwrap();
+        assert_eq!(decoded, None);
+    }
+}
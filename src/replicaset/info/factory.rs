@@ -7,9 +7,12 @@ use replisdk::agent::framework::NodeInfoFactoryArgs;
 
 use super::MongoInfo;
 use crate::conf::Conf;
+use crate::replicaset::ReplicaSetMode;
 
 /// Create instances of [`MongoInfo`] at the correct process initialisation time.
-pub struct MongoInfoFactory {}
+pub struct MongoInfoFactory {
+    pub(super) mode: ReplicaSetMode,
+}
 
 #[async_trait::async_trait]
 impl NodeInfoFactory for MongoInfoFactory {
@@ -21,13 +24,18 @@ impl NodeInfoFactory for MongoInfoFactory {
         let node_id = detect_node_id(args.conf, &args.telemetry.logger).await?;
 
         // Configure the store version detection strategies.
-        let version = super::version::configure_strategies(args.clone())?;
+        let client = crate::client::global();
+        let version = super::version::configure_strategies(args.clone(), "mongod", &client)?;
 
         // Create the MongoInfo instance.
-        let client = crate::client::global();
         Ok(MongoInfo {
+            attributes: MongoInfo::static_attributes(&self.mode),
             client,
+            cluster_role: Default::default(),
+            mode: self.mode.clone(),
             node_id,
+            server_status_sections: args.conf.custom.server_status_attributes.clone(),
+            status: super::snapshot::StatusSnapshot::new(args.conf.custom.status_snapshot_ttl),
             version,
         })
     }
@@ -0,0 +1,218 @@
+//! Model the replica set status of every member into a structured attribute.
+//!
+//! Any member of the replica set can report the state of its peers as it sees them,
+//! giving a view of the whole replica set even when other agents are not reachable.
+use anyhow::Context;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::constants::MemberState;
+use crate::errors::MongoInfoError;
+use crate::replicaset::info::optime::OpTime;
+
+/// Model the members list of the replica set status into a JSON array.
+///
+/// Each member is described by an object with the following attributes, when known:
+///
+/// - `id`: The `_id` of the member.
+/// - `host`: The `host:port` of the member.
+/// - `state`: The replica set state of the member (`PRIMARY`, `SECONDARY`, ...).
+/// - `health`: `true` if the member is reachable, `false` otherwise.
+/// - `self`: `true` for the member reporting the information.
+/// - `ping_ms`: Round trip time to the member, in milliseconds.
+/// - `sync_source`: The `host:port` of the member replicating from.
+/// - `optime`: The `ts` seconds and `increment` of the last operation applied by the member,
+///   and the election `term` it was applied in (replication protocol version 1 only).
+/// - `lag_secs`: How many seconds the member is behind the primary (healthy members only).
+/// - `lag_ops`: How many operations the member is behind the primary, when both optimes
+///   are in the same second (healthy members only).
+pub fn members(status: &Document) -> Result<Value> {
+    let members = status
+        .get_array("members")
+        .context(MongoInfoError::ReplicaSetStatusNoMembers)?;
+    let members: Vec<&Document> = members
+        .iter()
+        .map(|member| {
+            member
+                .as_document()
+                .ok_or(MongoInfoError::ReplicaSetStatusInvalidMember)
+        })
+        .collect::<Result<_, _>>()?;
+
+    // Lag is measured against the primary, if the replica set has one.
+    let primary_optime = members
+        .iter()
+        .find(|member| member.get_i32("state") == Ok(MemberState::Primary as i32))
+        .map(|primary| OpTime::from_member(primary))
+        .transpose()?
+        .flatten();
+
+    let members = members
+        .into_iter()
+        .map(|member| member_view(member, primary_optime.as_ref()))
+        .collect::<Result<_>>()?;
+    Ok(Value::Array(members))
+}
+
+/// Model a single member of the replica set status into a JSON object.
+fn member_view(member: &Document, primary_optime: Option<&OpTime>) -> Result<Value> {
+    let mut view = Map::new();
+    let id = member
+        .get_i32("_id")
+        .context(MongoInfoError::ReplicaSetStatusInvalidMember)?;
+    view.insert("id".into(), id.into());
+    let host = member
+        .get_str("name")
+        .context(MongoInfoError::ReplicaSetStatusInvalidMember)?;
+    view.insert("host".into(), host.into());
+
+    let state = member
+        .get_i32("state")
+        .unwrap_or(MemberState::Unknown as i32);
+    let state_name = match member.get_str("stateStr") {
+        Ok(name) => name.to_string(),
+        Err(_) => MemberState::try_from(state)
+            .map(|state| state.to_string())
+            .unwrap_or_else(|_| state.to_string()),
+    };
+    view.insert("state".into(), state_name.into());
+    let health = match member.get("health") {
+        Some(Bson::Double(health)) => *health >= 1.0,
+        Some(Bson::Int32(health)) => *health >= 1,
+        Some(Bson::Int64(health)) => *health >= 1,
+        _ => false,
+    };
+    view.insert("health".into(), health.into());
+    view.insert(
+        "self".into(),
+        member.get_bool("self").unwrap_or(false).into(),
+    );
+
+    // Optional attributes depend on the member and server version.
+    let ping = match member.get("pingMs") {
+        Some(Bson::Int32(ping)) => Some(i64::from(*ping)),
+        Some(Bson::Int64(ping)) => Some(*ping),
+        _ => None,
+    };
+    if let Some(ping) = ping {
+        view.insert("ping_ms".into(), ping.into());
+    }
+    let sync_source = member
+        .get_str("syncSourceHost")
+        .or_else(|_| member.get_str("syncingTo"))
+        .ok()
+        .filter(|source| !source.is_empty());
+    if let Some(sync_source) = sync_source {
+        view.insert("sync_source".into(), sync_source.into());
+    }
+
+    let optime = OpTime::from_member(member)?;
+    if let Some(optime) = &optime {
+        let mut ts = Map::new();
+        ts.insert("ts".into(), optime.ts.time.into());
+        ts.insert("increment".into(), optime.ts.increment.into());
+        if let Some(term) = optime.term {
+            ts.insert("term".into(), term.into());
+        }
+        view.insert("optime".into(), Value::Object(ts));
+    }
+
+    // Arbiters hold no data so they can't lag behind the primary.
+    // The optime of unreachable members is stale so their lag is unknown.
+    if health && state != MemberState::Arbiter as i32 {
+        if let (Some(primary), Some(optime)) = (primary_optime, optime) {
+            let lag = optime.lag_behind(primary);
+            view.insert("lag_secs".into(), lag.seconds.into());
+            if let Some(operations) = lag.operations {
+                view.insert("lag_ops".into(), operations.into());
+            }
+        }
+    }
+    Ok(Value::Object(view))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::DateTime;
+    use mongodb::bson::Timestamp;
+
+    use super::members;
+
+    #[test]
+    fn members_view() {
+        let status = mongodb::bson::doc! {
+            "set": "rs0",
+            "myState": 2,
+            "members": [{
+                "_id": 0,
+                "name": "mongo-0:27017",
+                "health": 1.0,
+                "state": 1,
+                "stateStr": "PRIMARY",
+                "optime": {"ts": Timestamp { time: 5, increment: 1 }, "t": 2_i64},
+                "optimeDate": DateTime::from_millis(5000),
+                "pingMs": 2_i64,
+            }, {
+                "_id": 1,
+                "name": "mongo-1:27017",
+                "health": 1.0,
+                "state": 2,
+                "stateStr": "SECONDARY",
+                "optime": Timestamp { time: 3, increment: 7 },
+                "optimeDate": DateTime::from_millis(3000),
+                "syncSourceHost": "mongo-0:27017",
+                "self": true,
+            }, {
+                "_id": 2,
+                "name": "mongo-2:27017",
+                "health": 0.0,
+                "state": 8,
+                "optimeDate": DateTime::from_millis(0),
+            }, {
+                "_id": 3,
+                "name": "mongo-3:27017",
+                "health": 1.0,
+                "state": 7,
+                "stateStr": "ARBITER",
+                "syncSourceHost": "",
+            }],
+        };
+        let view = members(&status).unwrap();
+        let expected = serde_json::json!([{
+            "id": 0,
+            "host": "mongo-0:27017",
+            "state": "PRIMARY",
+            "health": true,
+            "self": false,
+            "ping_ms": 2,
+            "optime": {"ts": 5, "increment": 1, "term": 2},
+            "lag_secs": 0,
+            "lag_ops": 0,
+        }, {
+            "id": 1,
+            "host": "mongo-1:27017",
+            "state": "SECONDARY",
+            "health": true,
+            "self": true,
+            "sync_source": "mongo-0:27017",
+            "optime": {"ts": 3, "increment": 7},
+            "lag_secs": 2,
+        }, {
+            "id": 2,
+            "host": "mongo-2:27017",
+            "state": "DOWN",
+            "health": false,
+            "self": false,
+        }, {
+            "id": 3,
+            "host": "mongo-3:27017",
+            "state": "ARBITER",
+            "health": true,
+            "self": false,
+        }]);
+        assert_eq!(view, expected);
+    }
+}
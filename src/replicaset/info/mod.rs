@@ -1,10 +1,12 @@
 //! NodeInfo implementation for ReplicaSet nodes.
+use std::sync::Arc;
+
 use anyhow::Context as AnyContext;
 use anyhow::Result;
 use mongodb::bson::Document;
 use mongodb::Client;
-use once_cell::sync::Lazy;
 use opentelemetry::trace::FutureExt;
+use tokio::sync::OnceCell;
 
 use replisdk::agent::framework::NodeInfo;
 use replisdk::agent::framework::StoreVersionChain;
@@ -17,134 +19,145 @@ use replisdk::context::Context;
 use replisdk::utils::metrics::CountFutureErrExt;
 use replisdk::utils::trace::TraceFutureErrExt;
 
-mod address;
+pub(crate) mod address;
 mod factory;
+mod members;
+pub(crate) mod oplog;
+pub(crate) mod optime;
+mod server_status;
 mod shard;
+mod sharding;
+mod snapshot;
 mod status;
-mod version;
+pub(crate) mod version;
 
 pub use self::factory::MongoInfoFactory;
 
-use crate::client::admin::replica_set_status;
+use crate::client::admin::server_status;
+use crate::conf::ServerStatusSection;
+use crate::constants::MemberState;
 use crate::constants::ATTRIBUTE_PREFIX;
-use crate::constants::CMD_COLL_STATS;
 use crate::constants::CMD_GET_PARAMETER;
 use crate::constants::DB_ADMIN;
-use crate::constants::DB_LOCAL;
 use crate::constants::FEATURE_COMPATIBILITY_VERSION;
 use crate::errors::MongoInfoError;
 use crate::metrics::observe_mongodb_op;
+use crate::replicaset::ReplicaSetMode;
 
 /// Store ID reported for nodes.
 const STORE_ID: &str = "mongo.replica";
 
-/// Set of never-changing agent attributes to include in responses.
-static STATIC_ATTRIBUTES: Lazy<AttributesMap> = Lazy::new(|| {
-    let mut attributes = AttributesMap::new();
-    attributes.insert(format!("{}/mode", ATTRIBUTE_PREFIX), "replica-set".into());
-    attributes
-});
-
 /// Gather MongoDB node information.
 #[derive(Clone, Debug)]
 pub struct MongoInfo {
+    attributes: AttributesMap,
     client: Client,
+    cluster_role: Arc<OnceCell<Option<String>>>,
+    mode: ReplicaSetMode,
     node_id: String,
+    server_status_sections: Option<Vec<ServerStatusSection>>,
+    status: self::snapshot::StatusSnapshot,
     version: StoreVersionChain,
 }
 
 impl MongoInfo {
-    /// Return the factory for [`MongoInfo`] instances.
-    pub fn factory() -> MongoInfoFactory {
-        MongoInfoFactory {}
+    /// Return the factory for [`MongoInfo`] instances running in the given mode.
+    pub fn factory(mode: ReplicaSetMode) -> MongoInfoFactory {
+        MongoInfoFactory { mode }
     }
-}
 
-impl MongoInfo {
-    /// Lookup MongoDB current feature compatibility version (FCV).
-    async fn feature_compatibility_version(&self) -> Result<String> {
-        let trace = crate::trace::mongodb_client_context(FEATURE_COMPATIBILITY_VERSION);
-        let (err_count, _timer) = observe_mongodb_op(FEATURE_COMPATIBILITY_VERSION);
-
-        let admin = self.client.database(DB_ADMIN);
-        let command = {
-            let mut command = Document::new();
-            command.insert(CMD_GET_PARAMETER, 1);
-            command.insert(FEATURE_COMPATIBILITY_VERSION, 1);
-            command
-        };
-
-        // Wrap the command to be traced into an anonymous future to decorate.
-        let observed = async {
-            let params = admin
-                .run_command(command)
-                .await
-                .context(MongoInfoError::FeatCompatVerUnknown)?;
-            match params.get_document(FEATURE_COMPATIBILITY_VERSION) {
-                Err(error) => {
-                    Err(anyhow::anyhow!(error).context(MongoInfoError::FeatCompatVerUnknown))
-                }
-                Ok(doc) => {
-                    let version = doc
-                        .get_str("version")
-                        .context(MongoInfoError::FeatCompatVerNotSet)?
-                        .to_string();
-                    Ok(version)
-                }
-            }
+    /// Set of never-changing agent attributes to include in responses.
+    fn static_attributes(mode: &ReplicaSetMode) -> AttributesMap {
+        let mode = match mode {
+            ReplicaSetMode::ConfigServer => "config-server",
+            ReplicaSetMode::ReplicaSet => "replica-set",
+            ReplicaSetMode::Shard => "shard",
         };
+        let mut attributes = AttributesMap::new();
+        attributes.insert(format!("{}/mode", ATTRIBUTE_PREFIX), mode.into());
+        attributes
+    }
 
-        // Decorate the operation once for all return clauses and execute.
-        observed
-            .count_on_err(err_count)
-            .trace_on_err_with_status()
-            .with_context(trace)
-            .await
+    /// Sharded cluster role of the node, looked up once and cached.
+    ///
+    /// The role is set on the command line so it can't change while the server runs.
+    async fn cluster_role(&self) -> Result<Option<String>> {
+        let role = self
+            .cluster_role
+            .get_or_try_init(|| self::sharding::cluster_role(&self.client))
+            .await?;
+        Ok(role.clone())
     }
+}
 
-    /// Lookup oplog collection max size.
-    async fn oplog_size(&self) -> Result<i64> {
-        let trace = crate::trace::mongodb_client_context(CMD_COLL_STATS);
-        let (err_count, _timer) = observe_mongodb_op(CMD_COLL_STATS);
+/// Lookup MongoDB current feature compatibility version (FCV).
+pub(crate) async fn feature_compatibility_version(client: &Client) -> Result<String> {
+    let trace = crate::trace::mongodb_client_context(FEATURE_COMPATIBILITY_VERSION);
+    let (err_count, _timer) = observe_mongodb_op(FEATURE_COMPATIBILITY_VERSION);
 
-        let command = {
-            let mut command = Document::new();
-            command.insert(CMD_COLL_STATS, "oplog.rs");
-            command
-        };
-        let local = self.client.database(DB_LOCAL);
-
-        // Wrap the command to be traced into an anonymous future to decorate.
-        let observed = async {
-            let stats = local
-                .run_command(command)
-                .await
-                .context(MongoInfoError::OplogStatsUnknown)?;
-            let max_size = stats
-                .get_i64("maxSize")
-                .context(MongoInfoError::OplogStatsNoSize)?;
-            Ok(max_size)
-        };
+    let admin = client.database(DB_ADMIN);
+    let command = {
+        let mut command = Document::new();
+        command.insert(CMD_GET_PARAMETER, 1);
+        command.insert(FEATURE_COMPATIBILITY_VERSION, 1);
+        command
+    };
 
-        // Decorate the operation once for all return clauses and execute.
-        observed
-            .count_on_err(err_count)
-            .trace_on_err_with_status()
-            .with_context(trace)
+    // Wrap the command to be traced into an anonymous future to decorate.
+    let observed = async {
+        let params = admin
+            .run_command(command)
             .await
-    }
+            .context(MongoInfoError::FeatCompatVerUnknown)?;
+        match params.get_document(FEATURE_COMPATIBILITY_VERSION) {
+            Err(error) => Err(anyhow::anyhow!(error).context(MongoInfoError::FeatCompatVerUnknown)),
+            Ok(doc) => {
+                let version = doc
+                    .get_str("version")
+                    .context(MongoInfoError::FeatCompatVerNotSet)?
+                    .to_string();
+                Ok(version)
+            }
+        }
+    };
+
+    // Decorate the operation once for all return clauses and execute.
+    observed
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
 }
 
 #[async_trait::async_trait]
 impl NodeInfo for MongoInfo {
     async fn node_info(&self, context: &Context) -> Result<Node> {
-        let rs = replica_set_status(&self.client).await;
+        let rs = self.status.get(&self.client).await;
         let node_status = self::status::get(rs, &context.logger).await?;
         let store_version = self.version.version(context).await?;
+
+        // Config servers report the sharded cluster role the node is running with.
+        let mut attributes = self.attributes.clone();
+        if let ReplicaSetMode::ConfigServer = self.mode {
+            if let Some(role) = self.cluster_role().await? {
+                attributes.insert(format!("{}/cluster-role", ATTRIBUTE_PREFIX), role.into());
+            }
+        }
+
+        // Report the remaining time the node was frozen for by the agent.
+        // The server does not report freezes so ones requested by other clients,
+        // or before the agent restarted, are not included.
+        if let Some(remaining) = crate::replicaset::state::freeze_remaining() {
+            attributes.insert(
+                format!("{}/agent-freeze.remaining-secs", ATTRIBUTE_PREFIX),
+                serde_json::Number::from(remaining.as_secs()).into(),
+            );
+        }
+
         let node = Node {
             address: self::address::detect()?,
             agent_version: crate::AGENT_VERSION.clone(),
-            attributes: STATIC_ATTRIBUTES.clone(),
+            attributes,
             node_id: self.node_id.clone(),
             node_status,
             store_id: STORE_ID.into(),
@@ -154,7 +167,9 @@ impl NodeInfo for MongoInfo {
     }
 
     async fn shards(&self, _: &Context) -> Result<ShardsInfo> {
-        let status = replica_set_status(&self.client)
+        let status = self
+            .status
+            .get(&self.client)
             .await
             .context(MongoInfoError::ReplicaSetStatusUnknown)?;
         let shard = shard::shard(status)?;
@@ -163,9 +178,11 @@ impl NodeInfo for MongoInfo {
         })
     }
 
-    async fn store_info(&self, _: &Context) -> Result<StoreExtras> {
+    async fn store_info(&self, context: &Context) -> Result<StoreExtras> {
         // Get the cluster ID from the RS status.
-        let status = replica_set_status(&self.client)
+        let status = self
+            .status
+            .get(&self.client)
             .await
             .context(MongoInfoError::ReplicaSetStatusUnknown)?;
         let name = status
@@ -173,20 +190,85 @@ impl NodeInfo for MongoInfo {
             .context(MongoInfoError::ReplicaSetStatusNoName)?;
 
         // Build additional attributes.
+        // Arbiters hold no data so oplog and FCV information is not available on them.
         let mut attributes = AttributesMap::new();
-        let oplog_size = self.oplog_size().await?;
-        attributes.insert(
-            format!("{}/oplog.size", ATTRIBUTE_PREFIX),
-            serde_json::Number::from(oplog_size).into(),
-        );
-        let feature_compat_ver = self.feature_compatibility_version().await?;
+        let arbiter = status.get_i32("myState") == Ok(MemberState::Arbiter as i32);
+        if !arbiter {
+            let oplog_stats = self::oplog::stats(&self.client).await?;
+            attributes.insert(
+                format!("{}/oplog.size", ATTRIBUTE_PREFIX),
+                serde_json::Number::from(oplog_stats.max_size).into(),
+            );
+            attributes.insert(
+                format!("{}/oplog.usage", ATTRIBUTE_PREFIX),
+                serde_json::Number::from(oplog_stats.size).into(),
+            );
+            if let Some(window) = self::oplog::window(&self.client).await? {
+                attributes.insert(
+                    format!("{}/oplog.window-secs", ATTRIBUTE_PREFIX),
+                    serde_json::Number::from(window.seconds()).into(),
+                );
+                attributes.insert(
+                    format!("{}/oplog.first-ts", ATTRIBUTE_PREFIX),
+                    serde_json::Number::from(window.first.time).into(),
+                );
+                attributes.insert(
+                    format!("{}/oplog.last-ts", ATTRIBUTE_PREFIX),
+                    serde_json::Number::from(window.last.time).into(),
+                );
+            }
+            let feature_compat_ver = feature_compatibility_version(&self.client).await?;
+            attributes.insert(
+                format!("{}/feature-compatibility", ATTRIBUTE_PREFIX),
+                feature_compat_ver.into(),
+            );
+        }
+
+        // Report the selected server status information.
+        // These attributes are informational so errors are logged and the attributes skipped.
+        let sections = self.server_status_sections.as_deref();
+        if !matches!(sections, Some(sections) if sections.is_empty()) {
+            match server_status(&self.client).await {
+                Ok(server_status) => {
+                    attributes.extend(self::server_status::attributes(&server_status, sections));
+                }
+                Err(error) => slog::warn!(
+                    context.logger, "Skipping serverStatus attributes after error";
+                    "server_error" => %error,
+                ),
+            }
+        }
+
+        // Report the state of all members as seen by this node.
         attributes.insert(
-            format!("{}/feature-compatibility", ATTRIBUTE_PREFIX),
-            feature_compat_ver.into(),
+            format!("{}/replication.members", ATTRIBUTE_PREFIX),
+            self::members::members(&status)?,
         );
 
+        // Shard members belong to the sharded cluster rather than the replica set.
+        // Arbiters do not store the shard identity so they can only report the replica set.
+        let mut cluster_id = name.to_string();
+        if matches!(self.mode, ReplicaSetMode::Shard) && !arbiter {
+            if let Some(role) = self.cluster_role().await? {
+                attributes.insert(format!("{}/cluster-role", ATTRIBUTE_PREFIX), role.into());
+            }
+            // Shards not added to a sharded cluster yet have no identity.
+            // Until then the replica set name is reported as the cluster ID.
+            if let Some(identity) = self::sharding::shard_identity(&self.client).await? {
+                attributes.insert(
+                    format!("{}/shard.name", ATTRIBUTE_PREFIX),
+                    identity.shard_name.into(),
+                );
+                attributes.insert(
+                    format!("{}/shard.config-servers", ATTRIBUTE_PREFIX),
+                    identity.config_servers.into(),
+                );
+                cluster_id = identity.cluster_id;
+            }
+        }
+
         Ok(StoreExtras {
-            cluster_id: name.to_string(),
+            cluster_id,
             attributes,
         })
     }
@@ -0,0 +1,163 @@
+//! Lookup oplog size, usage and window for Replica Set members.
+use anyhow::Context;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use mongodb::bson::Timestamp;
+use mongodb::Client;
+use opentelemetry::trace::FutureExt;
+
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureErrExt;
+
+use crate::constants::CMD_COLL_STATS;
+use crate::constants::COLL_OPLOG;
+use crate::constants::DB_LOCAL;
+use crate::errors::MongoInfoError;
+use crate::metrics::observe_mongodb_op;
+
+/// Name of the operation reported in telemetry when looking up oplog entries.
+const OP_OPLOG_ENTRIES: &str = "oplogEntries";
+
+/// Size information about the oplog collection.
+#[derive(Clone, Debug)]
+pub struct OplogStats {
+    /// Maximum size of the oplog, in bytes.
+    pub max_size: i64,
+
+    /// Current size of the oplog entries, in bytes.
+    pub size: i64,
+}
+
+/// Time range covered by the entries in the oplog.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OplogWindow {
+    /// Timestamp of the oldest entry in the oplog.
+    pub first: Timestamp,
+
+    /// Timestamp of the most recent entry in the oplog.
+    pub last: Timestamp,
+}
+
+impl OplogWindow {
+    /// Number of seconds between the first and last entries in the oplog.
+    ///
+    /// This is how long a member can fall behind before it needs a full resync.
+    pub fn seconds(&self) -> u32 {
+        self.last.time.saturating_sub(self.first.time)
+    }
+}
+
+/// Lookup oplog collection size and max size.
+pub async fn stats(client: &Client) -> Result<OplogStats> {
+    let trace = crate::trace::mongodb_client_context(CMD_COLL_STATS);
+    let (err_count, _timer) = observe_mongodb_op(CMD_COLL_STATS);
+
+    let command = {
+        let mut command = Document::new();
+        command.insert(CMD_COLL_STATS, COLL_OPLOG);
+        command
+    };
+    let local = client.database(DB_LOCAL);
+
+    // Wrap the command to be traced into an anonymous future to decorate.
+    let observed = async {
+        let stats = local
+            .run_command(command)
+            .await
+            .context(MongoInfoError::OplogStatsUnknown)?;
+        let max_size = stats
+            .get_i64("maxSize")
+            .context(MongoInfoError::OplogStatsNoSize)?;
+        let size = match stats.get("size") {
+            Some(Bson::Int32(size)) => i64::from(*size),
+            Some(Bson::Int64(size)) => *size,
+            Some(Bson::Double(size)) => *size as i64,
+            _ => anyhow::bail!(MongoInfoError::OplogStatsNoSize),
+        };
+        Ok(OplogStats { max_size, size })
+    };
+
+    // Decorate the operation once for all return clauses and execute.
+    observed
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+}
+
+/// Lookup the timestamps of the first and last entries in the oplog.
+///
+/// Returns `None` if the oplog is empty.
+pub async fn window(client: &Client) -> Result<Option<OplogWindow>> {
+    let trace = crate::trace::mongodb_client_context(OP_OPLOG_ENTRIES);
+    let (err_count, _timer) = observe_mongodb_op(OP_OPLOG_ENTRIES);
+
+    let oplog = client.database(DB_LOCAL).collection::<Document>(COLL_OPLOG);
+    let projection = mongodb::bson::doc! {"ts": 1};
+
+    // Wrap the command to be traced into an anonymous future to decorate.
+    let observed = async {
+        let first = oplog
+            .find_one(mongodb::bson::doc! {})
+            .sort(mongodb::bson::doc! {"$natural": 1})
+            .projection(projection.clone())
+            .await
+            .context(MongoInfoError::OplogEntriesUnknown)?;
+        let last = oplog
+            .find_one(mongodb::bson::doc! {})
+            .sort(mongodb::bson::doc! {"$natural": -1})
+            .projection(projection)
+            .await
+            .context(MongoInfoError::OplogEntriesUnknown)?;
+        let window = match (first, last) {
+            (Some(first), Some(last)) => Some(OplogWindow {
+                first: entry_timestamp(&first)?,
+                last: entry_timestamp(&last)?,
+            }),
+            _ => None,
+        };
+        Ok(window)
+    };
+
+    // Decorate the operation once for all return clauses and execute.
+    observed
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+}
+
+/// Extract the timestamp of an oplog entry.
+fn entry_timestamp(entry: &Document) -> Result<Timestamp> {
+    let ts = entry
+        .get_timestamp("ts")
+        .context(MongoInfoError::OplogEntryInvalid)?;
+    Ok(ts)
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+    use mongodb::bson::Timestamp;
+
+    use super::entry_timestamp;
+    use super::OplogWindow;
+
+    #[test]
+    fn window_seconds() {
+        let first = doc! {"ts": Timestamp { time: 1700000000, increment: 3 }};
+        let last = doc! {"ts": Timestamp { time: 1700086400, increment: 1 }};
+        let window = OplogWindow {
+            first: entry_timestamp(&first).unwrap(),
+            last: entry_timestamp(&last).unwrap(),
+        };
+        assert_eq!(window.seconds(), 86400);
+    }
+
+    #[test]
+    fn entry_without_timestamp() {
+        let entry = doc! {"op": "n"};
+        assert!(entry_timestamp(&entry).is_err());
+    }
+}
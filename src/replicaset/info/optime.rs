@@ -0,0 +1,225 @@
+//! Decode replica set member optimes and compare them.
+//!
+//! Members report the last operation applied as an optime:
+//!
+//! - With replication protocol version 1 the optime is a document with the
+//!   oplog entry timestamp (`ts`) and the election term (`t`).
+//! - With replication protocol version 0 (still available in MongoDB 3.6)
+//!   the optime is the oplog entry timestamp and there is no term.
+//!
+//! BSON timestamps are made of the seconds since the epoch and an increment counting
+//! operations within the same second, which makes them more precise than `optimeDate`.
+use anyhow::Context;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use mongodb::bson::Timestamp;
+use replisdk::agent::models::ShardCommitOffset;
+
+use crate::errors::MongoInfoError;
+
+/// Unit of commit offsets modelled on BSON timestamps.
+///
+/// With replication protocol version 1 the election term is appended to the unit
+/// (for example `bson-timestamp;term=3`) so offsets from different terms are not compared.
+pub const UNIT_BSON_TIMESTAMP: &str = "bson-timestamp";
+
+/// Optime of the last operation applied by a replica set member.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpTime {
+    /// Election term the operation was applied in (protocol version 1 only).
+    pub term: Option<i64>,
+
+    /// Timestamp of the operation in the oplog.
+    pub ts: Timestamp,
+}
+
+impl OpTime {
+    /// Decode the `optime` of a member in the output of `replSetGetStatus`.
+    ///
+    /// Returns `None` if the member does not report an optime.
+    pub fn from_member(member: &Document) -> Result<Option<OpTime>> {
+        let optime = match member.get("optime") {
+            None => return Ok(None),
+            Some(optime) => optime,
+        };
+        let optime = match optime {
+            Bson::Timestamp(ts) => OpTime {
+                term: None,
+                ts: *ts,
+            },
+            Bson::Document(optime) => {
+                let ts = optime
+                    .get_timestamp("ts")
+                    .context(MongoInfoError::ReplicaSetStatusInvalidOpTime)?;
+                let term = match optime.get("t") {
+                    Some(Bson::Int32(term)) => Some(i64::from(*term)),
+                    Some(Bson::Int64(term)) => Some(*term),
+                    _ => None,
+                };
+                OpTime { term, ts }
+            }
+            _ => anyhow::bail!(MongoInfoError::ReplicaSetStatusInvalidOpTime),
+        };
+        Ok(Some(optime))
+    }
+
+    /// Commit offset modelled on the optime timestamp and term.
+    ///
+    /// The timestamp seconds and increment are packed into a single non-negative value
+    /// so offsets within the same term compare like optimes:
+    /// the seconds take the upper 33 bits and the increment the lower 31 bits
+    /// (increments never get close to 2^31 operations in a second but are capped to be safe).
+    pub fn commit_offset(&self) -> ShardCommitOffset {
+        let increment = self.ts.increment.min(i32::MAX as u32);
+        let value = (i64::from(self.ts.time) << 31) | i64::from(increment);
+        let unit = match self.term {
+            None => UNIT_BSON_TIMESTAMP.to_string(),
+            Some(term) => format!("{};term={}", UNIT_BSON_TIMESTAMP, term),
+        };
+        ShardCommitOffset::unit(value, unit)
+    }
+
+    /// Lag of this optime behind the given (primary) optime.
+    pub fn lag_behind(&self, primary: &OpTime) -> OpTimeLag {
+        let seconds = i64::from(primary.ts.time) - i64::from(self.ts.time);
+        let operations = match seconds {
+            0 => Some((i64::from(primary.ts.increment) - i64::from(self.ts.increment)).max(0)),
+            _ => None,
+        };
+        OpTimeLag {
+            operations,
+            seconds: seconds.max(0),
+        }
+    }
+}
+
+/// Lag of a member optime behind the primary optime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpTimeLag {
+    /// Number of operations the member is behind by, if the optimes are in the same second.
+    ///
+    /// Operations are counted by timestamp increments, which restart every second,
+    /// so the number of operations across different seconds is not known.
+    pub operations: Option<i64>,
+
+    /// Number of seconds the member is behind by.
+    pub seconds: i64,
+}
+
+impl OpTimeLag {
+    /// Shard lag, always measured in seconds so it can be compared across samples.
+    pub fn shard_lag(&self) -> ShardCommitOffset {
+        ShardCommitOffset::seconds(self.seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+    use mongodb::bson::Timestamp;
+    use replisdk::agent::models::ShardCommitOffset;
+
+    use super::OpTime;
+    use super::OpTimeLag;
+    use super::UNIT_BSON_TIMESTAMP;
+
+    fn optime(time: u32, increment: u32) -> OpTime {
+        OpTime {
+            term: Some(1),
+            ts: Timestamp { time, increment },
+        }
+    }
+
+    #[test]
+    fn decode_protocol_v1() {
+        let member = doc! {"optime": {"ts": Timestamp { time: 10, increment: 2 }, "t": 3_i64}};
+        let optime = OpTime::from_member(&member).unwrap().unwrap();
+        assert_eq!(optime.term, Some(3));
+        assert_eq!(
+            optime.ts,
+            Timestamp {
+                time: 10,
+                increment: 2
+            }
+        );
+    }
+
+    #[test]
+    fn decode_protocol_v0() {
+        let member = doc! {"optime": Timestamp { time: 10, increment: 2 }};
+        let optime = OpTime::from_member(&member).unwrap().unwrap();
+        assert_eq!(optime.term, None);
+        assert_eq!(
+            optime.ts,
+            Timestamp {
+                time: 10,
+                increment: 2
+            }
+        );
+    }
+
+    #[test]
+    fn decode_missing() {
+        let optime = OpTime::from_member(&doc! {"_id": 1}).unwrap();
+        assert_eq!(optime, None);
+    }
+
+    #[test]
+    fn commit_offset_includes_increment() {
+        let offset = optime(1, 2).commit_offset();
+        assert_eq!(
+            offset,
+            ShardCommitOffset::unit((1 << 31) | 2, "bson-timestamp;term=1")
+        );
+        assert_ne!(offset, optime(1, 3).commit_offset());
+    }
+
+    #[test]
+    fn commit_offset_without_term() {
+        let optime = OpTime {
+            term: None,
+            ts: Timestamp {
+                time: 1,
+                increment: 2,
+            },
+        };
+        let offset = optime.commit_offset();
+        assert_eq!(
+            offset,
+            ShardCommitOffset::unit((1 << 31) | 2, UNIT_BSON_TIMESTAMP)
+        );
+    }
+
+    #[test]
+    fn commit_offset_is_not_negative() {
+        let offset = optime(u32::MAX, u32::MAX).commit_offset();
+        assert!(offset.value > 0);
+        assert!(offset.value > optime(u32::MAX - 1, u32::MAX).commit_offset().value);
+        assert!(optime(1 << 31, 0).commit_offset().value > optime(1, 0).commit_offset().value);
+    }
+
+    #[test]
+    fn lag_in_seconds_and_operations() {
+        let primary = optime(100, 7);
+        let lag = optime(90, 9).lag_behind(&primary);
+        let expected = OpTimeLag {
+            operations: None,
+            seconds: 10,
+        };
+        assert_eq!(lag, expected);
+        let lag = optime(100, 2).lag_behind(&primary);
+        let expected = OpTimeLag {
+            operations: Some(5),
+            seconds: 0,
+        };
+        assert_eq!(lag, expected);
+        assert_eq!(lag.shard_lag(), ShardCommitOffset::seconds(0));
+        let lag = primary.lag_behind(&primary);
+        let expected = OpTimeLag {
+            operations: Some(0),
+            seconds: 0,
+        };
+        assert_eq!(lag, expected);
+    }
+}
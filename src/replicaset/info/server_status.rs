@@ -0,0 +1,174 @@
+//! Extract store attributes from the output of the `serverStatus` command.
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+
+use replisdk::agent::models::AttributesMap;
+
+use crate::conf::ServerStatusSection;
+use crate::constants::ATTRIBUTE_PREFIX;
+
+/// Extract attributes for the selected sections of the server status.
+///
+/// If no sections are selected all sections are extracted.
+/// Values missing from the server status (for example because of the storage engine
+/// or server version) are skipped rather than treated as errors.
+pub fn attributes(status: &Document, sections: Option<&[ServerStatusSection]>) -> AttributesMap {
+    let mut attributes = AttributesMap::new();
+    let sections = sections.unwrap_or(ServerStatusSection::ALL);
+    for section in sections {
+        match section {
+            ServerStatusSection::Connections => connections(status, &mut attributes),
+            ServerStatusSection::Host => host(status, &mut attributes),
+            ServerStatusSection::StorageEngine => storage_engine(status, &mut attributes),
+            ServerStatusSection::Uptime => uptime(status, &mut attributes),
+            ServerStatusSection::WiredTigerCache => wired_tiger_cache(status, &mut attributes),
+        }
+    }
+    attributes
+}
+
+/// Current and available incoming connections.
+fn connections(status: &Document, attributes: &mut AttributesMap) {
+    let connections = match status.get_document("connections") {
+        Err(_) => return,
+        Ok(connections) => connections,
+    };
+    for field in ["current", "available"] {
+        if let Some(value) = integer(connections.get(field)) {
+            attributes.insert(
+                format!("{}/connections.{}", ATTRIBUTE_PREFIX, field),
+                value.into(),
+            );
+        }
+    }
+}
+
+/// Host and process the server is running as.
+fn host(status: &Document, attributes: &mut AttributesMap) {
+    if let Ok(host) = status.get_str("host") {
+        attributes.insert(format!("{}/host.name", ATTRIBUTE_PREFIX), host.into());
+    }
+    if let Ok(process) = status.get_str("process") {
+        attributes.insert(format!("{}/host.process", ATTRIBUTE_PREFIX), process.into());
+    }
+    if let Some(pid) = integer(status.get("pid")) {
+        attributes.insert(format!("{}/host.pid", ATTRIBUTE_PREFIX), pid.into());
+    }
+}
+
+/// Name of the storage engine in use.
+fn storage_engine(status: &Document, attributes: &mut AttributesMap) {
+    let name = status
+        .get_document("storageEngine")
+        .and_then(|engine| engine.get_str("name"));
+    if let Ok(name) = name {
+        attributes.insert(format!("{}/storage.engine", ATTRIBUTE_PREFIX), name.into());
+    }
+}
+
+/// Number of seconds the server has been running for.
+fn uptime(status: &Document, attributes: &mut AttributesMap) {
+    if let Some(uptime) = integer(status.get("uptime")) {
+        attributes.insert(format!("{}/uptime-secs", ATTRIBUTE_PREFIX), uptime.into());
+    }
+}
+
+/// Configured size and current usage of the WiredTiger cache, in bytes.
+fn wired_tiger_cache(status: &Document, attributes: &mut AttributesMap) {
+    let cache = match status
+        .get_document("wiredTiger")
+        .and_then(|wt| wt.get_document("cache"))
+    {
+        Err(_) => return,
+        Ok(cache) => cache,
+    };
+    let fields = [
+        ("maximum bytes configured", "cache-size"),
+        ("bytes currently in the cache", "cache-usage"),
+    ];
+    for (field, name) in fields {
+        if let Some(value) = integer(cache.get(field)) {
+            attributes.insert(
+                format!("{}/wiredtiger.{}", ATTRIBUTE_PREFIX, name),
+                value.into(),
+            );
+        }
+    }
+}
+
+/// Read a server status number as an integer, regardless of the BSON type used.
+pub(crate) fn integer(value: Option<&Bson>) -> Option<i64> {
+    match value {
+        Some(Bson::Int32(value)) => Some(i64::from(*value)),
+        Some(Bson::Int64(value)) => Some(*value),
+        Some(Bson::Double(value)) => Some(*value as i64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+    use mongodb::bson::Document;
+
+    use super::attributes;
+    use crate::conf::ServerStatusSection;
+
+    fn server_status() -> Document {
+        doc! {
+            "host": "mongo-0",
+            "version": "6.0.5",
+            "process": "mongod",
+            "pid": 42_i64,
+            "uptime": 3600.0,
+            "uptimeMillis": 3600123_i64,
+            "connections": {"current": 7, "available": 838853, "totalCreated": 12},
+            "storageEngine": {"name": "wiredTiger", "persistent": true},
+            "wiredTiger": {"cache": {
+                "maximum bytes configured": 268435456_i64,
+                "bytes currently in the cache": 1048576_i64,
+            }},
+            "ok": 1.0,
+        }
+    }
+
+    #[test]
+    fn all_sections() {
+        let attributes = attributes(&server_status(), None);
+        let attributes = serde_json::to_value(attributes).unwrap();
+        let expected = serde_json::json!({
+            "mongodb.com/connections.available": 838853,
+            "mongodb.com/connections.current": 7,
+            "mongodb.com/host.name": "mongo-0",
+            "mongodb.com/host.pid": 42,
+            "mongodb.com/host.process": "mongod",
+            "mongodb.com/storage.engine": "wiredTiger",
+            "mongodb.com/uptime-secs": 3600,
+            "mongodb.com/wiredtiger.cache-size": 268435456,
+            "mongodb.com/wiredtiger.cache-usage": 1048576,
+        });
+        assert_eq!(attributes, expected);
+    }
+
+    #[test]
+    fn selected_sections_only() {
+        let sections = [ServerStatusSection::StorageEngine];
+        let attributes = attributes(&server_status(), Some(&sections));
+        let attributes = serde_json::to_value(attributes).unwrap();
+        let expected = serde_json::json!({
+            "mongodb.com/storage.engine": "wiredTiger",
+        });
+        assert_eq!(attributes, expected);
+    }
+
+    #[test]
+    fn missing_sections_skipped() {
+        let status = doc! {"host": "mongo-0", "ok": 1.0};
+        let sections = [
+            ServerStatusSection::Connections,
+            ServerStatusSection::WiredTigerCache,
+        ];
+        let attributes = attributes(&status, Some(&sections));
+        assert!(attributes.is_empty());
+    }
+}
@@ -9,6 +9,8 @@ use replisdk::agent::models::ShardRole;
 
 use crate::constants::MemberState;
 use crate::errors::MongoInfoError;
+use crate::replicaset::info::optime::OpTime;
+use crate::replicaset::info::optime::UNIT_BSON_TIMESTAMP;
 
 /// Model the replica set status into a [`Shard`].
 pub fn shard(status: Document) -> Result<Shard> {
@@ -59,33 +61,130 @@ pub fn shard(status: Document) -> Result<Shard> {
         .get_str("name")
         .context(MongoInfoError::ReplicaSetStatusInvalidSelf)?
         .to_string();
-    //  - Current node optime (as Commit Offset).
-    let optime = my_self
-        .get_datetime("optimeDate")
-        .context(MongoInfoError::ReplicaSetStatusInvalidSelf)?
-        .timestamp_millis();
     //  - Replica Set member state (as Role).
     let role = my_self
         .get_i32("state")
         .context(MongoInfoError::ReplicaSetStatusInvalidSelf)?;
     let role = MemberState::try_from(role)?;
+
+    // Arbiters hold no data so they have no optime to report.
+    if let MemberState::Arbiter = role {
+        return Ok(Shard {
+            commit_offset: ShardCommitOffset::unit(0, UNIT_BSON_TIMESTAMP),
+            lag: None,
+            role: ShardRole::from(role),
+            shard_id,
+        });
+    }
+
+    //  - Current node optime (as Commit Offset).
+    let optime =
+        OpTime::from_member(my_self)?.ok_or(MongoInfoError::ReplicaSetStatusInvalidSelf)?;
     let role = ShardRole::from(role);
     //  - Delta between primary node and current member.
-    let lag = if let Some(primary) = primary {
-        let primary_optime = primary
-            .get_datetime("optimeDate")
-            .context(MongoInfoError::ReplicaSetStatusInvalidSelf)?
-            .timestamp_millis();
-        let lag = ShardCommitOffset::milliseconds(primary_optime - optime);
-        Some(lag)
-    } else {
-        None
+    let lag = match primary {
+        None => None,
+        Some(primary) => {
+            OpTime::from_member(primary)?.map(|primary| optime.lag_behind(&primary).shard_lag())
+        }
     };
 
     Ok(Shard {
-        commit_offset: ShardCommitOffset::milliseconds(optime),
+        commit_offset: optime.commit_offset(),
         lag,
         role,
         shard_id,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::Timestamp;
+    use replisdk::agent::models::ShardCommitOffset;
+    use replisdk::agent::models::ShardRole;
+
+    use super::shard;
+    use crate::replicaset::info::optime::UNIT_BSON_TIMESTAMP;
+
+    #[test]
+    fn arbiter_has_no_lag() {
+        let status = mongodb::bson::doc! {
+            "set": "rs0",
+            "myState": 7,
+            "members": [{
+                "_id": 0,
+                "name": "mongo-0:27017",
+                "state": 1,
+                "optime": {"ts": Timestamp { time: 1, increment: 1 }, "t": 1_i64},
+                "optimeDate": mongodb::bson::DateTime::from_millis(1000),
+            }, {
+                "_id": 1,
+                "name": "mongo-1:27017",
+                "state": 7,
+                "self": true,
+            }],
+        };
+        let shard = shard(status).unwrap();
+        assert_eq!(
+            shard.commit_offset,
+            ShardCommitOffset::unit(0, UNIT_BSON_TIMESTAMP)
+        );
+        assert_eq!(shard.lag, None);
+        assert_eq!(shard.role, ShardRole::Other("ARBITER".into()));
+    }
+
+    #[test]
+    fn secondary_lag() {
+        let status = mongodb::bson::doc! {
+            "set": "rs0",
+            "myState": 2,
+            "members": [{
+                "_id": 0,
+                "name": "mongo-0:27017",
+                "state": 1,
+                "optime": {"ts": Timestamp { time: 3, increment: 4 }, "t": 1_i64},
+                "optimeDate": mongodb::bson::DateTime::from_millis(3000),
+            }, {
+                "_id": 1,
+                "name": "mongo-1:27017",
+                "state": 2,
+                "optime": {"ts": Timestamp { time: 1, increment: 2 }, "t": 1_i64},
+                "optimeDate": mongodb::bson::DateTime::from_millis(1000),
+                "self": true,
+            }],
+        };
+        let shard = shard(status).unwrap();
+        assert_eq!(
+            shard.commit_offset,
+            ShardCommitOffset::unit((1 << 31) | 2, "bson-timestamp;term=1")
+        );
+        assert_eq!(shard.lag, Some(ShardCommitOffset::seconds(2)));
+        assert_eq!(shard.role, ShardRole::Secondary);
+    }
+
+    #[test]
+    fn secondary_lag_protocol_v0() {
+        let status = mongodb::bson::doc! {
+            "set": "rs0",
+            "myState": 2,
+            "members": [{
+                "_id": 0,
+                "name": "mongo-0:27017",
+                "state": 1,
+                "optime": Timestamp { time: 3, increment: 9 },
+            }, {
+                "_id": 1,
+                "name": "mongo-1:27017",
+                "state": 2,
+                "optime": Timestamp { time: 3, increment: 4 },
+                "self": true,
+            }],
+        };
+        let shard = shard(status).unwrap();
+        assert_eq!(
+            shard.commit_offset,
+            ShardCommitOffset::unit((3 << 31) | 4, UNIT_BSON_TIMESTAMP)
+        );
+        assert_eq!(shard.lag, Some(ShardCommitOffset::seconds(0)));
+    }
+}
@@ -0,0 +1,112 @@
+//! Lookup sharded cluster information for Replica Set members.
+use anyhow::Context;
+use anyhow::Result;
+use mongodb::bson::Document;
+use mongodb::Client;
+use opentelemetry::trace::FutureExt;
+
+use replisdk::utils::metrics::CountFutureErrExt;
+use replisdk::utils::trace::TraceFutureErrExt;
+
+use crate::constants::CMD_GET_CMD_LINE_OPTS;
+use crate::constants::COLL_SYSTEM_VERSION;
+use crate::constants::DB_ADMIN;
+use crate::constants::SHARD_IDENTITY;
+use crate::errors::MongoInfoError;
+use crate::metrics::observe_mongodb_op;
+
+/// Identity of a shard within a sharded cluster.
+#[derive(Clone, Debug)]
+pub struct ShardIdentity {
+    /// ID of the sharded cluster the shard is part of.
+    pub cluster_id: String,
+
+    /// Connection string to the config server replica set.
+    pub config_servers: String,
+
+    /// Name of the shard within the sharded cluster.
+    pub shard_name: String,
+}
+
+/// Lookup the sharded cluster role of the node from its command line options.
+///
+/// Nodes that are not part of a sharded cluster have no role and `None` is returned.
+pub async fn cluster_role(client: &Client) -> Result<Option<String>> {
+    let trace = crate::trace::mongodb_client_context(CMD_GET_CMD_LINE_OPTS);
+    let (err_count, _timer) = observe_mongodb_op(CMD_GET_CMD_LINE_OPTS);
+
+    let admin = client.database(DB_ADMIN);
+    let command = mongodb::bson::doc! {CMD_GET_CMD_LINE_OPTS: 1};
+
+    // Wrap the command to be traced into an anonymous future to decorate.
+    let observed = async {
+        let opts = admin
+            .run_command(command)
+            .await
+            .context(MongoInfoError::CmdLineOptsUnknown)?;
+        let role = opts
+            .get_document("parsed")
+            .and_then(|parsed| parsed.get_document("sharding"))
+            .and_then(|sharding| sharding.get_str("clusterRole"))
+            .ok()
+            .map(String::from);
+        Ok(role)
+    };
+
+    // Decorate the operation once for all return clauses and execute.
+    observed
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+}
+
+/// Lookup the identity of the shard the node is a member of.
+///
+/// The identity document is stored by MongoDB on shard members when they are
+/// added to a sharded cluster so `None` is returned until then.
+pub async fn shard_identity(client: &Client) -> Result<Option<ShardIdentity>> {
+    let trace = crate::trace::mongodb_client_context(SHARD_IDENTITY);
+    let (err_count, _timer) = observe_mongodb_op(SHARD_IDENTITY);
+
+    let versions = client
+        .database(DB_ADMIN)
+        .collection::<Document>(COLL_SYSTEM_VERSION);
+    let filter = mongodb::bson::doc! {"_id": SHARD_IDENTITY};
+
+    // Wrap the command to be traced into an anonymous future to decorate.
+    let observed = async {
+        let identity = match versions
+            .find_one(filter)
+            .await
+            .context(MongoInfoError::ShardIdentityUnknown)?
+        {
+            None => return Ok(None),
+            Some(identity) => identity,
+        };
+        let cluster_id = identity
+            .get_object_id("clusterId")
+            .context(MongoInfoError::ShardIdentityInvalid)?
+            .to_hex();
+        let config_servers = identity
+            .get_str("configsvrConnectionString")
+            .context(MongoInfoError::ShardIdentityInvalid)?
+            .to_string();
+        let shard_name = identity
+            .get_str("shardName")
+            .context(MongoInfoError::ShardIdentityInvalid)?
+            .to_string();
+        Ok(Some(ShardIdentity {
+            cluster_id,
+            config_servers,
+            shard_name,
+        }))
+    };
+
+    // Decorate the operation once for all return clauses and execute.
+    observed
+        .count_on_err(err_count)
+        .trace_on_err_with_status()
+        .with_context(trace)
+        .await
+}
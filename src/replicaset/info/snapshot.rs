@@ -0,0 +1,103 @@
+//! Short-lived cache of the replica set status shared by all node information requests.
+//!
+//! Replicante requests node, shards and store information separately but at about the same time.
+//! Sharing one [`replSetGetStatus`] result across these requests reduces load on the server
+//! and ensures responses are based on a consistent view of the replica set.
+//!
+//! [`replSetGetStatus`]: https://www.mongodb.com/docs/manual/reference/command/replSetGetStatus/
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use mongodb::bson::Document;
+use mongodb::error::Result as MdbResult;
+use mongodb::Client;
+use tokio::sync::Mutex;
+
+use crate::client::admin::replica_set_status;
+
+/// Default time a replica set status snapshot is reused for, in milliseconds.
+const DEFAULT_TTL_MS: u64 = 1000;
+
+/// Short-lived cache of the replica set status.
+#[derive(Clone, Debug)]
+pub struct StatusSnapshot {
+    cached: Arc<Mutex<Option<CachedStatus>>>,
+    ttl: Duration,
+}
+
+impl StatusSnapshot {
+    /// Cache replica set status results for the given number of milliseconds.
+    ///
+    /// If no TTL is given a default of one second is used.
+    /// A TTL of zero disables caching.
+    pub fn new(ttl_ms: Option<u64>) -> StatusSnapshot {
+        let ttl = Duration::from_millis(ttl_ms.unwrap_or(DEFAULT_TTL_MS));
+        StatusSnapshot {
+            cached: Arc::new(Mutex::new(None)),
+            ttl,
+        }
+    }
+
+    /// Return the cached replica set status, fetching a new one if the cache has expired.
+    ///
+    /// Errors are cached too so that all requests observe the same node state.
+    /// Concurrent callers wait for a single fetch instead of issuing their own.
+    pub async fn get(&self, client: &Client) -> MdbResult<Document> {
+        let mut cached = self.cached.lock().await;
+        let now = Instant::now();
+        if let Some(status) = cached
+            .as_ref()
+            .and_then(|cached| cached.fresh(now, self.ttl))
+        {
+            return status;
+        }
+
+        let status = replica_set_status(client).await;
+        if !self.ttl.is_zero() {
+            *cached = Some(CachedStatus {
+                fetched: now,
+                status: status.clone(),
+            });
+        }
+        status
+    }
+}
+
+/// Replica set status result and the time it was fetched at.
+#[derive(Debug)]
+struct CachedStatus {
+    fetched: Instant,
+    status: MdbResult<Document>,
+}
+
+impl CachedStatus {
+    /// Return a copy of the cached status if it has not expired yet.
+    fn fresh(&self, now: Instant, ttl: Duration) -> Option<MdbResult<Document>> {
+        if now.saturating_duration_since(self.fetched) >= ttl {
+            return None;
+        }
+        Some(self.status.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use super::CachedStatus;
+
+    #[test]
+    fn cached_status_expires() {
+        let fetched = Instant::now();
+        let cached = CachedStatus {
+            fetched,
+            status: Ok(mongodb::bson::doc! {"set": "rs0"}),
+        };
+        let ttl = Duration::from_secs(1);
+        let status = cached.fresh(fetched + Duration::from_millis(500), ttl);
+        assert_eq!(status.unwrap().unwrap(), mongodb::bson::doc! {"set": "rs0"});
+        assert!(cached.fresh(fetched + ttl, ttl).is_none());
+    }
+}
@@ -2,7 +2,6 @@
 use anyhow::Result;
 use mongodb::bson::Document;
 use mongodb::error::Error;
-use mongodb::error::ErrorKind;
 use mongodb::error::Result as MdbResult;
 use slog::Logger;
 
@@ -21,20 +20,32 @@ pub async fn get(result: MdbResult<Document>, logger: &Logger) -> Result<NodeSta
         }
     };
 
-    // Determine the node status based on the replica set status.
+    let maintenance = crate::replicaset::state::maintenance_requested();
+    Ok(status_for_state(&status, maintenance))
+}
+
+/// Determine the [`NodeStatus`] based on the response to the `replSetGetStatus` command.
+///
+/// Nodes RECOVERING because the agent put them in maintenance mode are intentionally
+/// unavailable and are not reported as unhealthy.
+/// Maintenance mode requested by other clients can't be told apart from other reasons
+/// to be RECOVERING so these nodes are still reported as unhealthy.
+fn status_for_state(status: &Document, agent_maintenance: bool) -> NodeStatus {
     let state = status.get_i32("myState").unwrap_or(6);
     let state = match MemberState::try_from(state) {
         Ok(state) => state,
-        Err(error) => {
-            let status = NodeStatus::Unknown(error.to_string());
-            return Ok(status);
-        }
+        Err(error) => return NodeStatus::Unknown(error.to_string()),
     };
-    let state = match state {
+    match state {
+        MemberState::Recovering
+            if agent_maintenance && crate::client::admin::maintenance_requests(status) > 0 =>
+        {
+            NodeStatus::Unavailable
+        }
         MemberState::Startup | MemberState::Recovering | MemberState::Rollback => {
             NodeStatus::Unhealthy
         }
-        MemberState::Primary | MemberState::Secondary => NodeStatus::Healthy,
+        MemberState::Arbiter | MemberState::Primary | MemberState::Secondary => NodeStatus::Healthy,
         MemberState::Startup2 => NodeStatus::JoiningCluster,
         MemberState::Removed => NodeStatus::NotInCluster,
         state => {
@@ -44,21 +55,13 @@ pub async fn get(result: MdbResult<Document>, logger: &Logger) -> Result<NodeSta
             );
             NodeStatus::Unknown(state)
         }
-    };
-    Ok(state)
+    }
 }
 
 /// Determine the [`NodeStatus`] based on the error response to the `replSetGetStatus` command.
 async fn status_for_error(error: Error) -> Result<NodeStatus> {
     // Check for connection related errors, suggesting the store process is down.
-    let is_connection_error = matches!(
-        *error.kind,
-        ErrorKind::Authentication { .. }
-            | ErrorKind::ConnectionPoolCleared { .. }
-            | ErrorKind::Io(_)
-            | ErrorKind::ServerSelection { .. }
-    );
-    if is_connection_error {
+    if crate::client::admin::connection_failed(&error) {
         return Ok(NodeStatus::Unavailable);
     }
 
@@ -71,3 +74,38 @@ async fn status_for_error(error: Error) -> Result<NodeStatus> {
     let message = error.to_string();
     Ok(NodeStatus::Unknown(message))
 }
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::doc;
+
+    use replisdk::agent::models::NodeStatus;
+
+    use super::status_for_state;
+
+    #[test]
+    fn recovering_is_unhealthy() {
+        let status = status_for_state(&doc! {"myState": 3}, false);
+        assert_eq!(status, NodeStatus::Unhealthy);
+        let status = status_for_state(&doc! {"myState": 3}, true);
+        assert_eq!(status, NodeStatus::Unhealthy);
+    }
+
+    #[test]
+    fn maintenance_is_unavailable() {
+        let status = status_for_state(&doc! {"myState": 3, "maintenanceMode": 1}, true);
+        assert_eq!(status, NodeStatus::Unavailable);
+    }
+
+    #[test]
+    fn maintenance_by_others_is_unhealthy() {
+        let status = status_for_state(&doc! {"myState": 3, "maintenanceMode": 1}, false);
+        assert_eq!(status, NodeStatus::Unhealthy);
+    }
+
+    #[test]
+    fn secondary_is_healthy() {
+        let status = status_for_state(&doc! {"myState": 2}, false);
+        assert_eq!(status, NodeStatus::Healthy);
+    }
+}
@@ -1,5 +1,9 @@
 //! Detect the store version for Replica Set members.
+use anyhow::Context as AnyContext;
 use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+use mongodb::Client;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
@@ -8,13 +12,96 @@ use replisdk::agent::framework::StoreVersionChain;
 use replisdk::agent::framework::StoreVersionCommand;
 use replisdk::agent::framework::StoreVersionCommandConf;
 use replisdk::agent::framework::StoreVersionFile;
+use replisdk::agent::framework::StoreVersionStrategy;
 use replisdk::agent::models::StoreVersion;
+use replisdk::context::Context;
+
+use crate::conf::VersionStrategy;
 
 static BUILD_INFO_EXTRACT: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"(?s)Build Info: (\{.*\})")
         .expect("MongoDB build info regular expression failed to compile")
 });
 
+static PLAIN_VERSION_EXTRACT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^(?:db|mongos) version v(\S+)\s*$")
+        .expect("MongoDB plain version regular expression failed to compile")
+});
+
+static PLAIN_GIT_VERSION_EXTRACT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^git version: (\S+)\s*$")
+        .expect("MongoDB plain git version regular expression failed to compile")
+});
+
+/// Percona Server for MongoDB versions have a release number suffix (for example `3.6.23-13.0`).
+static PERCONA_VERSION_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\d+\.\d+\.\d+-\d+(\.\d+)?$")
+        .expect("Percona version regular expression failed to compile")
+});
+
+static PLAIN_MODULES_EXTRACT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^modules: (.*?)\s*$")
+        .expect("MongoDB plain modules regular expression failed to compile")
+});
+
+/// Name of the [`StoreVersion::extra`] attribute reporting the MongoDB edition.
+const EXTRA_EDITION: &str = "edition";
+
+/// Fields of the `buildInfo` command output not included in [`StoreVersion::extra`].
+///
+/// The version and git version are reported as the version number and checkout,
+/// the other fields describe the command response rather than the server build.
+const BUILD_INFO_SKIP: [&str; 5] = [
+    "$clusterTime",
+    "gitVersion",
+    "ok",
+    "operationTime",
+    "version",
+];
+
+/// Edition of MongoDB the server was built as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edition {
+    Community,
+    Enterprise,
+    Percona,
+}
+
+impl Edition {
+    /// Detect the MongoDB edition from the list of modules the server was built with.
+    ///
+    /// Percona Server for MongoDB also reports its own version (in the build information
+    /// or as the version release suffix), which is used to detect it when no Percona
+    /// module is listed.
+    fn detect<'a, M>(modules: M, psmdb_version: bool) -> Edition
+    where
+        M: IntoIterator<Item = &'a str>,
+    {
+        let mut edition = Edition::Community;
+        for module in modules {
+            let module = module.to_lowercase();
+            if module.contains("percona") {
+                return Edition::Percona;
+            }
+            if module == "enterprise" {
+                edition = Edition::Enterprise;
+            }
+        }
+        if psmdb_version {
+            return Edition::Percona;
+        }
+        edition
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Edition::Community => "community",
+            Edition::Enterprise => "enterprise",
+            Edition::Percona => "percona",
+        }
+    }
+}
+
 /// MongoD build information data returned parsed out of `mongod --version`.
 #[derive(Debug, serde::Deserialize)]
 struct MongoBuildInfo {
@@ -27,11 +114,11 @@ struct MongoBuildInfo {
     version: String,
 }
 
-/// Default command to detect the mongod version.
-fn default_command_conf() -> StoreVersionCommandConf {
+/// Default command to detect the mongod (or mongos) version.
+fn default_command_conf(command: &str) -> StoreVersionCommandConf {
     StoreVersionCommandConf {
         args: vec!["--version".into()],
-        command: "mongod".into(),
+        command: command.into(),
         env: Default::default(),
     }
 }
@@ -56,14 +143,31 @@ fn mongod_version_decode(data: Vec<u8>) -> Result<StoreVersion> {
     // ```
     let data = String::from_utf8(data)?;
     let build_info = match BUILD_INFO_EXTRACT.captures(&data) {
-        None => anyhow::bail!(VersionNotInOutput {}),
         Some(info) => info,
+        None => match mongod_plain_version_decode(&data)? {
+            None => anyhow::bail!(VersionNotInOutput {}),
+            Some(version) => return Ok(version),
+        },
     };
     let build_info = build_info
         .get(1)
         .expect("regex matched but capture group not found")
         .as_str();
-    let build_info: MongoBuildInfo = serde_json::from_str(build_info)?;
+    let mut build_info: MongoBuildInfo = serde_json::from_str(build_info)?;
+    if let serde_json::Value::Object(ref mut extra) = build_info.extra {
+        let modules = extra
+            .get("modules")
+            .and_then(|modules| modules.as_array())
+            .map(|modules| {
+                modules
+                    .iter()
+                    .filter_map(|module| module.as_str())
+                    .collect()
+            })
+            .unwrap_or_else(Vec::new);
+        let edition = Edition::detect(modules, extra.contains_key("psmdbVersion"));
+        extra.insert(EXTRA_EDITION.into(), edition.as_str().into());
+    }
     let extra = if build_info.extra.is_null() {
         None
     } else {
@@ -77,35 +181,170 @@ fn mongod_version_decode(data: Vec<u8>) -> Result<StoreVersion> {
     })
 }
 
+/// Decode the plain line format of `mongod --version` into a [`StoreVersion`].
+///
+/// This format is used by older servers and some third-party builds.
+fn mongod_plain_version_decode(data: &str) -> Result<Option<StoreVersion>> {
+    // Example output this function parses.
+    // ```
+    // db version v3.6.23
+    // git version: d352e6a4764659e0d0350ce77279de3c1f243e5c
+    // OpenSSL version: OpenSSL 1.0.2g  1 Mar 2016
+    // allocator: tcmalloc
+    // modules: none
+    // build environment:
+    //     distmod: ubuntu1604
+    //     distarch: x86_64
+    //     target_arch: x86_64
+    // ```
+    let number = match PLAIN_VERSION_EXTRACT.captures(data) {
+        None => return Ok(None),
+        Some(version) => version
+            .get(1)
+            .expect("regex matched but capture group not found")
+            .as_str()
+            .to_string(),
+    };
+    let checkout = PLAIN_GIT_VERSION_EXTRACT.captures(data).map(|git| {
+        git.get(1)
+            .expect("regex matched but capture group not found")
+            .as_str()
+            .to_string()
+    });
+
+    // Modules are listed space separated, with "none" for no modules.
+    let modules: Vec<&str> = PLAIN_MODULES_EXTRACT
+        .captures(data)
+        .and_then(|modules| modules.get(1))
+        .map(|modules| modules.as_str())
+        .filter(|modules| *modules != "none")
+        .map(|modules| modules.split_whitespace().collect())
+        .unwrap_or_default();
+    let percona = PERCONA_VERSION_MATCH.is_match(&number);
+    let edition = Edition::detect(modules.iter().copied(), percona);
+
+    let mut extra = serde_json::Map::new();
+    extra.insert("modules".into(), modules.into());
+    extra.insert(EXTRA_EDITION.into(), edition.as_str().into());
+    let extra = serde_json::to_string(&extra)?;
+    Ok(Some(StoreVersion {
+        checkout,
+        extra: Some(extra),
+        number,
+    }))
+}
+
+/// Detect the version of the server the agent is connected to with the [`buildInfo`] command.
+///
+/// Unlike other strategies this does not require access to the MongoDB binary or files,
+/// for example when the agent runs in a different container than the server.
+///
+/// [`buildInfo`]: https://www.mongodb.com/docs/manual/reference/command/buildInfo/
+#[derive(Debug)]
+pub struct StoreVersionBuildInfo {
+    client: Client,
+}
+
+impl StoreVersionBuildInfo {
+    /// Detect the version of the server the given client is connected to.
+    pub fn new(client: Client) -> StoreVersionBuildInfo {
+        StoreVersionBuildInfo { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl StoreVersionStrategy for StoreVersionBuildInfo {
+    async fn version(&self, _: &Context) -> Result<StoreVersion> {
+        let build_info = crate::client::admin::build_info(&self.client)
+            .await
+            .context(BuildInfoFailed {})?;
+        build_info_decode(&build_info)
+    }
+}
+
+/// Decode the output of the `buildInfo` command into a [`StoreVersion`].
+///
+/// The extra version information matches the `mongod --version` build information:
+/// all build fields except the version and git version, plus the detected edition.
+fn build_info_decode(build_info: &Document) -> Result<StoreVersion> {
+    let number = build_info
+        .get_str("version")
+        .context(VersionNotInOutput {})?
+        .to_string();
+    let checkout = build_info.get_str("gitVersion").ok().map(String::from);
+
+    let mut extra = serde_json::Map::new();
+    for (key, value) in build_info {
+        if BUILD_INFO_SKIP.contains(&key.as_str()) {
+            continue;
+        }
+        let value = Bson::into_relaxed_extjson(value.clone());
+        extra.insert(key.clone(), value);
+    }
+    let modules = build_info
+        .get_array("modules")
+        .map(|modules| {
+            modules
+                .iter()
+                .filter_map(|module| module.as_str())
+                .collect()
+        })
+        .unwrap_or_else(|_| Vec::new());
+    let edition = Edition::detect(modules, build_info.contains_key("psmdbVersion"));
+    extra.insert(EXTRA_EDITION.into(), edition.as_str().into());
+    let extra = serde_json::to_string(&extra)?;
+    Ok(StoreVersion {
+        checkout,
+        extra: Some(extra),
+        number,
+    })
+}
+
+/// Unable to detect the version with the buildInfo command.
+#[derive(Debug, thiserror::Error)]
+#[error("unable to detect the version with the buildInfo command")]
+pub struct BuildInfoFailed {}
+
 /// Unable to find version information.
 #[derive(Debug, thiserror::Error)]
 #[error("unable to find version information")]
 pub struct VersionNotInOutput {}
 
 /// Configure the store version detection strategies.
+///
+/// The `command` is the MongoDB binary to run if no version command is configured.
+/// The `client` is used to query the server with the `buildInfo` strategy.
 pub fn configure_strategies(
     args: NodeInfoFactoryArgs<'_, crate::conf::Conf>,
+    command: &str,
+    client: &Client,
 ) -> Result<StoreVersionChain> {
-    let chain = StoreVersionChain::default();
-
-    // Try checking the mongod command first.
-    let conf = args
-        .conf
-        .custom
-        .version_detect
-        .command
-        .clone()
-        .unwrap_or_else(default_command_conf);
-    let strategy = StoreVersionCommand::with_conf(conf)
-        .decode(mongod_version_decode)
-        .finish();
-    let chain = chain.strategy(strategy);
-
-    // Try checking a version file after.
-    let mut chain = chain;
-    if let Some(ref path) = args.conf.custom.version_detect.file {
-        let strategy = StoreVersionFile::new(path).decode(mongod_version_decode);
-        chain = chain.strategy(strategy);
+    let detect = &args.conf.custom.version_detect;
+    let mut chain = StoreVersionChain::default();
+    for strategy in &detect.order {
+        chain = match strategy {
+            VersionStrategy::BuildInfo => {
+                chain.strategy(StoreVersionBuildInfo::new(client.clone()))
+            }
+            VersionStrategy::Command => {
+                let conf = detect
+                    .command
+                    .clone()
+                    .unwrap_or_else(|| default_command_conf(command));
+                let strategy = StoreVersionCommand::with_conf(conf)
+                    .decode(mongod_version_decode)
+                    .finish();
+                chain.strategy(strategy)
+            }
+            // The file strategy is only used if a file is configured.
+            VersionStrategy::File => match detect.file {
+                None => chain,
+                Some(ref path) => {
+                    let strategy = StoreVersionFile::new(path).decode(mongod_version_decode);
+                    chain.strategy(strategy)
+                }
+            },
+        };
     }
 
     Ok(chain)
@@ -113,6 +352,7 @@ pub fn configure_strategies(
 
 #[cfg(test)]
 mod tests {
+    use super::build_info_decode;
     use super::mongod_version_decode;
 
     const BUILD_INFO: &str = r#"db version v4.4.13
@@ -129,6 +369,79 @@ mod tests {
         }
     }"#;
 
+    const BUILD_INFO_ENTERPRISE: &str = r#"db version v5.0.15
+    Build Info: {
+        "version": "5.0.15",
+        "gitVersion": "935639beed3d0c19c2551c93854b831107c0b118",
+        "openSSLVersion": "OpenSSL 1.1.1f  31 Mar 2020",
+        "modules": [
+            "enterprise"
+        ],
+        "allocator": "tcmalloc",
+        "environment": {
+            "distmod": "ubuntu2004",
+            "distarch": "x86_64",
+            "target_arch": "x86_64"
+        }
+    }"#;
+
+    const BUILD_INFO_PERCONA: &str = r#"db version v4.4.13-13
+    Build Info: {
+        "version": "4.4.13-13",
+        "gitVersion": "7f2fd2b2c4ddf6e7c4bbd47b4a0e2a8a3b05a4f7",
+        "openSSLVersion": "OpenSSL 1.1.1f  31 Mar 2020",
+        "modules": [],
+        "allocator": "tcmalloc",
+        "psmdbVersion": "4.4.13-13",
+        "environment": {
+            "distarch": "x86_64",
+            "target_arch": "x86_64"
+        }
+    }"#;
+
+    const PLAIN_VERSION: &str = "db version v3.6.23
+git version: d352e6a4764659e0d0350ce77279de3c1f243e5c
+OpenSSL version: OpenSSL 1.0.2g  1 Mar 2016
+allocator: tcmalloc
+modules: none
+build environment:
+    distmod: ubuntu1604
+    distarch: x86_64
+    target_arch: x86_64
+";
+
+    const PLAIN_VERSION_ENTERPRISE: &str = "db version v3.6.8
+git version: 6bc9ed599c3fa164703346a22bad17e33fa913e4
+OpenSSL version: OpenSSL 1.0.2g  1 Mar 2016
+allocator: tcmalloc
+modules: enterprise
+build environment:
+    distmod: ubuntu1604
+    distarch: x86_64
+    target_arch: x86_64
+";
+
+    const PLAIN_VERSION_PERCONA: &str = "db version v3.6.23-13.0
+git version: 9cd6ba1d3a0e3a8d3f3ac1fc0f2b6dc9e2ed8d6c
+OpenSSL version: OpenSSL 1.1.1f  31 Mar 2020
+allocator: tcmalloc
+modules: none
+build environment:
+    distarch: x86_64
+    target_arch: x86_64
+";
+
+    const PLAIN_VERSION_MONGOS: &str = "mongos version v3.6.23
+git version: d352e6a4764659e0d0350ce77279de3c1f243e5c
+OpenSSL version: OpenSSL 1.0.2g  1 Mar 2016
+allocator: tcmalloc
+modules: none
+build environment:
+    distmod: ubuntu1604
+    distarch: x86_64
+    target_arch: x86_64
+";
+
     #[test]
     fn build_info_extracted() {
         let data = Vec::from(BUILD_INFO);
@@ -141,7 +454,7 @@ mod tests {
         assert_eq!(
             version.extra,
             Some(
-                r#"{"openSSLVersion":"OpenSSL 1.1.1f  31 Mar 2020","modules":[],"allocator":"tcmalloc","environment":{"distmod":"ubuntu2004","distarch":"x86_64","target_arch":"x86_64"}}"#.into()
+                r#"{"openSSLVersion":"OpenSSL 1.1.1f  31 Mar 2020","modules":[],"allocator":"tcmalloc","environment":{"distmod":"ubuntu2004","distarch":"x86_64","target_arch":"x86_64"},"edition":"community"}"#.into()
             )
         )
     }
@@ -155,4 +468,133 @@ mod tests {
             Err(error) => panic!("expected VersionNotInOutput error, got error {:?}", error),
         }
     }
+
+    #[test]
+    fn build_info_command_decoded() {
+        let build_info = mongodb::bson::doc! {
+            "version": "6.0.5",
+            "gitVersion": "c9a99c120371d4d4c52cbb15dac34a36ce8d3b1d",
+            "modules": ["enterprise"],
+            "allocator": "tcmalloc",
+            "storageEngines": ["devnull", "ephemeralForTest", "inMemory", "wiredTiger"],
+            "maxBsonObjectSize": 16777216,
+            "ok": 1.0,
+            "$clusterTime": {"clusterTime": mongodb::bson::Timestamp { time: 1, increment: 1 }},
+            "operationTime": mongodb::bson::Timestamp { time: 1, increment: 1 },
+        };
+        let version = build_info_decode(&build_info).unwrap();
+        assert_eq!(
+            version.checkout,
+            Some("c9a99c120371d4d4c52cbb15dac34a36ce8d3b1d".into())
+        );
+        assert_eq!(version.number, "6.0.5".to_string());
+        assert_eq!(
+            version.extra,
+            Some(
+                r#"{"modules":["enterprise"],"allocator":"tcmalloc","storageEngines":["devnull","ephemeralForTest","inMemory","wiredTiger"],"maxBsonObjectSize":16777216,"edition":"enterprise"}"#.into()
+            )
+        )
+    }
+
+    #[test]
+    fn build_info_command_without_version() {
+        let build_info = mongodb::bson::doc! {"ok": 1.0};
+        let error = build_info_decode(&build_info).unwrap_err();
+        assert!(error.is::<super::VersionNotInOutput>());
+    }
+
+    #[test]
+    fn build_info_enterprise() {
+        let data = Vec::from(BUILD_INFO_ENTERPRISE);
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(version.number, "5.0.15".to_string());
+        assert_eq!(edition(&version), "enterprise");
+    }
+
+    #[test]
+    fn build_info_percona() {
+        let data = Vec::from(BUILD_INFO_PERCONA);
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(
+            version.checkout,
+            Some("7f2fd2b2c4ddf6e7c4bbd47b4a0e2a8a3b05a4f7".into())
+        );
+        assert_eq!(version.number, "4.4.13-13".to_string());
+        assert_eq!(edition(&version), "percona");
+    }
+
+    #[test]
+    fn plain_version_community() {
+        let data = Vec::from(PLAIN_VERSION);
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(
+            version.checkout,
+            Some("d352e6a4764659e0d0350ce77279de3c1f243e5c".into())
+        );
+        assert_eq!(version.number, "3.6.23".to_string());
+        assert_eq!(
+            version.extra,
+            Some(r#"{"modules":[],"edition":"community"}"#.into())
+        );
+    }
+
+    #[test]
+    fn plain_version_enterprise() {
+        let data = Vec::from(PLAIN_VERSION_ENTERPRISE);
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(version.number, "3.6.8".to_string());
+        assert_eq!(
+            version.extra,
+            Some(r#"{"modules":["enterprise"],"edition":"enterprise"}"#.into())
+        );
+    }
+
+    #[test]
+    fn plain_version_percona() {
+        let data = Vec::from(PLAIN_VERSION_PERCONA);
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(
+            version.checkout,
+            Some("9cd6ba1d3a0e3a8d3f3ac1fc0f2b6dc9e2ed8d6c".into())
+        );
+        assert_eq!(version.number, "3.6.23-13.0".to_string());
+        assert_eq!(edition(&version), "percona");
+    }
+
+    #[test]
+    fn plain_version_mongos() {
+        let data = Vec::from(PLAIN_VERSION_MONGOS);
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(
+            version.checkout,
+            Some("d352e6a4764659e0d0350ce77279de3c1f243e5c".into())
+        );
+        assert_eq!(version.number, "3.6.23".to_string());
+        assert_eq!(edition(&version), "community");
+    }
+
+    #[test]
+    fn plain_version_pre_release() {
+        let data = Vec::from("db version v4.4.0-rc13\n");
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(version.number, "4.4.0-rc13".to_string());
+        assert_eq!(edition(&version), "community");
+    }
+
+    #[test]
+    fn plain_version_without_git() {
+        let data = Vec::from("db version v3.6.0\n");
+        let version = mongod_version_decode(data).unwrap();
+        assert_eq!(version.checkout, None);
+        assert_eq!(version.number, "3.6.0".to_string());
+    }
+
+    fn edition(version: &replisdk::agent::models::StoreVersion) -> String {
+        let extra = version
+            .extra
+            .as_ref()
+            .expect("version has no extra information");
+        let extra: serde_json::Value = serde_json::from_str(extra).unwrap();
+        extra["edition"].as_str().unwrap().to_string()
+    }
 }
@@ -1,4 +1,6 @@
 //! Agent for MongoDB nodes running in a Replica Set cluster.
+//!
+//! The same agent also manages members of Shard and Config Server Replica Sets in Sharded clusters.
 use anyhow::Result;
 
 use replisdk::agent::framework::Agent;
@@ -9,8 +11,22 @@ use replisdk::runtime::telemetry::TelemetryOptions;
 use crate::conf::Conf;
 use crate::Cli;
 
-mod actions;
-mod info;
+pub(crate) mod actions;
+pub(crate) mod info;
+pub(crate) mod state;
+
+/// Modes the agent can manage Replica Set members in.
+#[derive(Clone, Debug)]
+pub enum ReplicaSetMode {
+    /// Members of a Sharded cluster config servers Replica Set.
+    ConfigServer,
+
+    /// Members of a Replica Set cluster.
+    ReplicaSet,
+
+    /// Members of a Shard Replica Set in a Sharded cluster.
+    Shard,
+}
 
 /// Explicitly typed Agent builder for MongoDB agents.
 ///
@@ -21,8 +37,8 @@ type MongoAgent = Agent<Conf, info::MongoInfoFactory>;
 /// Configuration of MongoDB agents.
 type MongoConf = AgentConf<Conf>;
 
-/// Run a Replicante Agent for MongoDB nodes in ReplicaSet clusters.
-pub fn run(args: Cli) -> Result<()> {
+/// Run a Replicante Agent for MongoDB nodes in ReplicaSet clusters (or shards).
+pub fn run(args: Cli, mode: ReplicaSetMode) -> Result<()> {
     let mut conf = crate::conf::load(&args.config, MongoConf::default())?;
     crate::conf::apply_overrides(&mut conf.custom)?;
     conf.runtime
@@ -30,10 +46,10 @@ pub fn run(args: Cli) -> Result<()> {
         .clone()
         .into_runtime()
         .expect("failed configuration of tokio runtime")
-        .block_on(async_run(args, conf))
+        .block_on(async_run(args, conf, mode))
 }
 
-async fn async_run(_args: Cli, conf: MongoConf) -> Result<()> {
+async fn async_run(_args: Cli, conf: MongoConf, mode: ReplicaSetMode) -> Result<()> {
     let options = AgentOptions {
         requests_metrics_prefix: "repliagent",
     };
@@ -46,12 +62,35 @@ async fn async_run(_args: Cli, conf: MongoConf) -> Result<()> {
         .configure(conf)
         .options(options)
         .telemetry_options(telemetry)
-        .node_info(info::MongoInfo::factory())
+        .node_info(info::MongoInfo::factory(mode.clone()))
         .initialise_with(crate::client::Initialise)
-        .initialise_with(crate::metrics::Register)
+        .initialise_with(crate::metrics::Register::for_replica_set())
+        .initialise_with(crate::metrics::RegisterOplog)
         .register_actions(replisdk::agent::framework::actions::wellknown::test::all())
         .register_action(actions::cluster::Add::metadata())
-        .register_action(actions::cluster::Init::metadata());
+        .register_action(actions::cluster::ForceReconfig::metadata())
+        .register_action(actions::cluster::Init::metadata(&mode))
+        .register_action(actions::cluster::Remove::metadata())
+        .register_action(actions::cluster::StepDown::metadata())
+        .register_action(actions::cluster::UpdateMember::metadata())
+        .register_action(actions::node::Freeze::metadata())
+        .register_action(actions::node::MaintenanceEnter::metadata())
+        .register_action(actions::node::MaintenanceLeave::metadata())
+        .register_action(actions::node::ResizeOplog::metadata());
+
+    // Config server replica sets do not support arbiters.
+    let agent = match mode {
+        ReplicaSetMode::ConfigServer => agent,
+        ReplicaSetMode::ReplicaSet | ReplicaSetMode::Shard => {
+            agent.register_action(actions::cluster::AddArbiter::metadata())
+        }
+    };
+
+    // The FCV of sharded clusters is set through mongos routers.
+    let agent = match mode {
+        ReplicaSetMode::ReplicaSet => agent.register_action(actions::cluster::SetFcv::metadata()),
+        ReplicaSetMode::ConfigServer | ReplicaSetMode::Shard => agent,
+    };
 
     // Run the agent until error or shutdown.
     agent.run().await
@@ -0,0 +1,76 @@
+//! Track node state changes requested by agent actions.
+//!
+//! Some changes requested with MongoDB commands are not reported back by the server.
+//! Actions record them here so node information can include them.
+//! This state is kept in memory and is lost when the agent restarts.
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+
+/// Singleton record of node state changes requested by the agent.
+static REQUESTED_STATE: Lazy<RwLock<RequestedState>> =
+    Lazy::new(|| RwLock::new(RequestedState::default()));
+
+/// Node state changes requested by the agent.
+#[derive(Debug, Default)]
+struct RequestedState {
+    /// Time until which the node is not eligible to become primary.
+    frozen_until: Option<Instant>,
+
+    /// The agent requested the node to enter maintenance mode and did not undo it yet.
+    maintenance: bool,
+}
+
+/// Record the node was frozen for the given duration (a zero duration unfreezes it).
+pub fn freeze(duration: Duration) {
+    let mut state = REQUESTED_STATE
+        .write()
+        .expect("REQUESTED_STATE RwLock poisoned");
+    state.frozen_until = match duration.is_zero() {
+        true => None,
+        false => Some(Instant::now() + duration),
+    };
+}
+
+/// Remaining time the node is frozen for, if the agent froze it.
+pub fn freeze_remaining() -> Option<Duration> {
+    let state = REQUESTED_STATE
+        .read()
+        .expect("REQUESTED_STATE RwLock poisoned");
+    state
+        .frozen_until
+        .map(|until| until.saturating_duration_since(Instant::now()))
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// Record the agent requested (or undid its request for) the node to enter maintenance mode.
+pub fn maintenance(requested: bool) {
+    let mut state = REQUESTED_STATE
+        .write()
+        .expect("REQUESTED_STATE RwLock poisoned");
+    state.maintenance = requested;
+}
+
+/// Check if the agent has an outstanding request for the node to enter maintenance mode.
+pub fn maintenance_requested() -> bool {
+    let state = REQUESTED_STATE
+        .read()
+        .expect("REQUESTED_STATE RwLock poisoned");
+    state.maintenance
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn freeze_and_unfreeze() {
+        super::freeze(Duration::from_secs(60));
+        let remaining = super::freeze_remaining().unwrap();
+        assert!(remaining > Duration::from_secs(50));
+        super::freeze(Duration::ZERO);
+        assert_eq!(super::freeze_remaining(), None);
+    }
+}
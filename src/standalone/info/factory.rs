@@ -0,0 +1,41 @@
+//! Factory for StandaloneInfo instances.
+use anyhow::Result;
+
+use replisdk::agent::framework::detect_node_id;
+use replisdk::agent::framework::NodeInfoFactory;
+use replisdk::agent::framework::NodeInfoFactoryArgs;
+
+use super::StandaloneInfo;
+use crate::conf::Conf;
+
+/// Create instances of [`StandaloneInfo`] at the correct process initialisation time.
+pub struct StandaloneInfoFactory {}
+
+#[async_trait::async_trait]
+impl NodeInfoFactory for StandaloneInfoFactory {
+    type Conf = Conf;
+    type NodeInfo = StandaloneInfo;
+
+    async fn factory<'a>(
+        &self,
+        args: NodeInfoFactoryArgs<'a, Self::Conf>,
+    ) -> Result<StandaloneInfo> {
+        // Grab identifiers to report from the API.
+        let node_id = detect_node_id(args.conf, &args.telemetry.logger).await?;
+
+        // Configure the store version detection strategies.
+        let client = crate::client::global();
+        let version = crate::replicaset::info::version::configure_strategies(
+            args.clone(),
+            "mongod",
+            &client,
+        )?;
+
+        // Create the StandaloneInfo instance.
+        Ok(StandaloneInfo {
+            client,
+            node_id,
+            version,
+        })
+    }
+}
@@ -0,0 +1,92 @@
+//! NodeInfo implementation for standalone nodes.
+use anyhow::Context as AnyContext;
+use anyhow::Result;
+use mongodb::Client;
+use once_cell::sync::Lazy;
+
+use replisdk::agent::framework::NodeInfo;
+use replisdk::agent::framework::StoreVersionChain;
+use replisdk::agent::framework::StoreVersionStrategy;
+use replisdk::agent::models::AttributesMap;
+use replisdk::agent::models::Node;
+use replisdk::agent::models::ShardsInfo;
+use replisdk::agent::models::StoreExtras;
+use replisdk::context::Context;
+
+mod factory;
+mod shard;
+mod status;
+
+pub use self::factory::StandaloneInfoFactory;
+
+use crate::client::admin::server_status;
+use crate::constants::ATTRIBUTE_PREFIX;
+use crate::errors::MongoInfoError;
+
+/// Store ID reported for nodes.
+const STORE_ID: &str = "mongo.standalone";
+
+/// Set of never-changing agent attributes to include in responses.
+static STATIC_ATTRIBUTES: Lazy<AttributesMap> = Lazy::new(|| {
+    let mut attributes = AttributesMap::new();
+    attributes.insert(format!("{}/mode", ATTRIBUTE_PREFIX), "standalone".into());
+    attributes
+});
+
+/// Gather standalone MongoDB node information.
+#[derive(Clone, Debug)]
+pub struct StandaloneInfo {
+    client: Client,
+    node_id: String,
+    version: StoreVersionChain,
+}
+
+impl StandaloneInfo {
+    /// Return the factory for [`StandaloneInfo`] instances.
+    pub fn factory() -> StandaloneInfoFactory {
+        StandaloneInfoFactory {}
+    }
+}
+
+#[async_trait::async_trait]
+impl NodeInfo for StandaloneInfo {
+    async fn node_info(&self, context: &Context) -> Result<Node> {
+        let status = server_status(&self.client).await;
+        let node_status = self::status::get(status, &context.logger).await?;
+        let store_version = self.version.version(context).await?;
+        let node = Node {
+            address: crate::replicaset::info::address::detect()?,
+            agent_version: crate::AGENT_VERSION.clone(),
+            attributes: STATIC_ATTRIBUTES.clone(),
+            node_id: self.node_id.clone(),
+            node_status,
+            store_id: STORE_ID.into(),
+            store_version,
+        };
+        Ok(node)
+    }
+
+    async fn shards(&self, _: &Context) -> Result<ShardsInfo> {
+        let status = server_status(&self.client)
+            .await
+            .context(MongoInfoError::ServerStatusUnknown)?;
+        let shard = shard::shard(&status)?;
+        Ok(ShardsInfo {
+            shards: vec![shard],
+        })
+    }
+
+    async fn store_info(&self, _: &Context) -> Result<StoreExtras> {
+        // Standalone nodes are a cluster of one, identified by the server host.
+        let status = server_status(&self.client)
+            .await
+            .context(MongoInfoError::ServerStatusUnknown)?;
+        let host = status
+            .get_str("host")
+            .context(MongoInfoError::ServerStatusNoHost)?;
+        Ok(StoreExtras {
+            cluster_id: host.to_string(),
+            attributes: AttributesMap::new(),
+        })
+    }
+}
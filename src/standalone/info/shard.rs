@@ -0,0 +1,66 @@
+//! Model the server status into a [`Shard`].
+use anyhow::Context;
+use anyhow::Result;
+use mongodb::bson::Bson;
+use mongodb::bson::Document;
+
+use replisdk::agent::models::Shard;
+use replisdk::agent::models::ShardCommitOffset;
+use replisdk::agent::models::ShardRole;
+
+use crate::errors::MongoInfoError;
+
+/// Model the server status into a [`Shard`].
+///
+/// Standalone nodes have no oplog or last write time to derive a commit offset from.
+/// Instead the server uptime is reported as an offset that increases while the server runs
+/// (it restarts from zero when the server restarts).
+pub fn shard(status: &Document) -> Result<Shard> {
+    let shard_id = status
+        .get_str("host")
+        .context(MongoInfoError::ServerStatusNoHost)?
+        .to_string();
+    let uptime = match status.get("uptimeMillis") {
+        Some(Bson::Int64(uptime)) => *uptime,
+        Some(Bson::Int32(uptime)) => i64::from(*uptime),
+        _ => anyhow::bail!(MongoInfoError::ServerStatusNoUptime),
+    };
+    Ok(Shard {
+        commit_offset: ShardCommitOffset::milliseconds(uptime),
+        lag: None,
+        role: ShardRole::Primary,
+        shard_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use replisdk::agent::models::ShardCommitOffset;
+    use replisdk::agent::models::ShardRole;
+
+    use super::shard;
+
+    #[test]
+    fn shard_from_server_status() {
+        let status = mongodb::bson::doc! {
+            "host": "mongo-0:27017",
+            "uptimeMillis": 4242_i64,
+            "ok": 1,
+        };
+        let shard = shard(&status).unwrap();
+        assert_eq!(shard.shard_id, "mongo-0:27017");
+        assert_eq!(shard.commit_offset, ShardCommitOffset::milliseconds(4242));
+        assert_eq!(shard.role, ShardRole::Primary);
+        assert_eq!(shard.lag, None);
+    }
+
+    #[test]
+    fn shard_without_uptime() {
+        let status = mongodb::bson::doc! {"host": "mongo-0:27017", "ok": 1};
+        let error = shard(&status).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(crate::errors::MongoInfoError::ServerStatusNoUptime)
+        ));
+    }
+}
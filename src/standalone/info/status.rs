@@ -0,0 +1,25 @@
+//! Detect the node status for standalone nodes.
+use anyhow::Result;
+use mongodb::bson::Document;
+use mongodb::error::Result as MdbResult;
+use slog::Logger;
+
+use replisdk::agent::models::NodeStatus;
+
+/// Get the current [`NodeStatus`] of the managed node based on the serverStatus command.
+pub async fn get(result: MdbResult<Document>, logger: &Logger) -> Result<NodeStatus> {
+    let error = match result {
+        Ok(_) => return Ok(NodeStatus::Healthy),
+        Err(error) => error,
+    };
+    slog::debug!(logger, "Error executing serverStatus"; "server_error" => %error);
+
+    // Check for connection related errors, suggesting the store process is down.
+    if crate::client::admin::connection_failed(&error) {
+        return Ok(NodeStatus::Unavailable);
+    }
+
+    // Consider all other errors unknown.
+    let message = error.to_string();
+    Ok(NodeStatus::Unknown(message))
+}
@@ -0,0 +1,61 @@
+//! Agent for standalone (non-replicated) MongoDB nodes.
+use anyhow::Result;
+
+use replisdk::agent::framework::Agent;
+use replisdk::agent::framework::AgentConf;
+use replisdk::agent::framework::AgentOptions;
+use replisdk::runtime::telemetry::TelemetryOptions;
+
+use crate::conf::Conf;
+use crate::replicaset::actions;
+use crate::replicaset::ReplicaSetMode;
+use crate::Cli;
+
+mod info;
+
+/// Explicitly typed Agent builder for standalone MongoDB agents.
+///
+/// Having this explicit type can defined decorator functions to set up the agent
+/// and surface type-related issues quickly and more clearly.
+type StandaloneAgent = Agent<Conf, info::StandaloneInfoFactory>;
+
+/// Configuration of standalone MongoDB agents.
+type StandaloneConf = AgentConf<Conf>;
+
+/// Run a Replicante Agent for standalone MongoDB nodes.
+pub fn run(args: Cli) -> Result<()> {
+    let mut conf = crate::conf::load(&args.config, StandaloneConf::default())?;
+    crate::conf::apply_overrides(&mut conf.custom)?;
+    conf.runtime
+        .tokio
+        .clone()
+        .into_runtime()
+        .expect("failed configuration of tokio runtime")
+        .block_on(async_run(args, conf))
+}
+
+async fn async_run(_args: Cli, conf: StandaloneConf) -> Result<()> {
+    let options = AgentOptions {
+        requests_metrics_prefix: "repliagent",
+    };
+    let telemetry = TelemetryOptions::for_sentry_release(crate::RELEASE_ID)
+        .for_app(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        .finish();
+
+    // Configure the agent process using the `Agent` builder.
+    // The cluster init action converts nodes restarted with `--replSet` into replica sets.
+    let agent = StandaloneAgent::build()
+        .configure(conf)
+        .options(options)
+        .telemetry_options(telemetry)
+        .node_info(info::StandaloneInfo::factory())
+        .initialise_with(crate::client::Initialise)
+        .initialise_with(crate::metrics::Register::default())
+        .register_actions(replisdk::agent::framework::actions::wellknown::test::all())
+        .register_action(actions::cluster::Init::metadata(
+            &ReplicaSetMode::ReplicaSet,
+        ));
+
+    // Run the agent until error or shutdown.
+    agent.run().await
+}